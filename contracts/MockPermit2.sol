@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.26;
+
+import "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+
+/// @notice Local stand-in for Uniswap's Permit2 SignatureTransfer.
+/// Mirrors the permitTransferFrom ABI, deadline and unordered nonce rules but
+/// skips signature verification, so any non-empty signature is accepted.
+contract MockPermit2 {
+    struct TokenPermissions {
+        address token;
+        uint256 amount;
+    }
+
+    struct PermitTransferFrom {
+        TokenPermissions permitted;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    struct SignatureTransferDetails {
+        address to;
+        uint256 requestedAmount;
+    }
+
+    error SignatureExpired(uint256 signatureDeadline);
+    error InvalidAmount(uint256 maxAmount);
+    error InvalidNonce();
+    error InvalidSignature();
+
+    mapping(address => mapping(uint256 => uint256)) public nonceBitmap;
+
+    function permitTransferFrom(
+        PermitTransferFrom memory permit,
+        SignatureTransferDetails calldata transferDetails,
+        address owner,
+        bytes calldata signature
+    ) external {
+        if (block.timestamp > permit.deadline) revert SignatureExpired(permit.deadline);
+        if (transferDetails.requestedAmount > permit.permitted.amount) revert InvalidAmount(permit.permitted.amount);
+        if (signature.length == 0) revert InvalidSignature();
+
+        uint256 wordPos = permit.nonce >> 8;
+        uint256 bit = 1 << (permit.nonce & 0xff);
+        uint256 flipped = nonceBitmap[owner][wordPos] ^= bit;
+        if (flipped & bit == 0) revert InvalidNonce();
+
+        require(
+            IERC20(permit.permitted.token).transferFrom(owner, transferDetails.to, transferDetails.requestedAmount),
+            "transfer failed"
+        );
+    }
+}
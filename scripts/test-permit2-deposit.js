@@ -0,0 +1,133 @@
+const hre = require("hardhat");
+
+// End-to-end check of createEscrowWithPermit2 against MockPermit2.
+// Needs a SecureFlow Stylus deployment on a local Nitro dev node, initialized by
+// the same account that runs this script:
+//   SECUREFLOW_ADDRESS=0x... npx hardhat run scripts/test-permit2-deposit.js --network localhost
+
+const SECUREFLOW_ABI = [
+  "function setPermit2(address permit2)",
+  "function permit2() view returns (address)",
+  "function whitelistToken(address token)",
+  "function escrowedAmount(address token) view returns (uint256)",
+  "function createEscrowWithPermit2(address beneficiary, address[] arbiters, uint8 required_confirmations, uint256[] milestone_amounts, string[] milestone_descriptions, address token, uint256 duration, string project_title, string project_description, uint256 permit_nonce, uint256 permit_deadline, bytes signature) returns (uint256)",
+];
+
+function check(condition, message) {
+  if (!condition) {
+    throw new Error(message);
+  }
+  console.log("  ✓", message);
+}
+
+async function main() {
+  const secureFlowAddress = process.env.SECUREFLOW_ADDRESS;
+  if (!secureFlowAddress) {
+    throw new Error("SECUREFLOW_ADDRESS environment variable is not set");
+  }
+
+  const [depositor, beneficiary] = await hre.ethers.getSigners();
+  console.log("🧪 Permit2 deposit test");
+  console.log("Depositor:", depositor.address);
+  console.log("Beneficiary:", beneficiary.address);
+
+  const secureFlow = new hre.ethers.Contract(secureFlowAddress, SECUREFLOW_ABI, depositor);
+
+  console.log("\n📝 Deploying MockERC20 and MockPermit2...");
+  const MockERC20 = await hre.ethers.getContractFactory("MockERC20");
+  const token = await MockERC20.deploy("Mock Token", "MOCK", hre.ethers.parseEther("1000000"));
+  await token.waitForDeployment();
+  const tokenAddress = await token.getAddress();
+
+  const MockPermit2 = await hre.ethers.getContractFactory("MockPermit2");
+  const permit2 = await MockPermit2.deploy();
+  await permit2.waitForDeployment();
+  const permit2Address = await permit2.getAddress();
+  console.log("MockERC20:", tokenAddress);
+  console.log("MockPermit2:", permit2Address);
+
+  console.log("\n🔧 Pointing SecureFlow at the mock...");
+  await (await secureFlow.setPermit2(permit2Address)).wait();
+  await (await secureFlow.whitelistToken(tokenAddress)).wait();
+  check((await secureFlow.permit2()) === permit2Address, "permit2() returns the mock");
+
+  // Permit2 pulls the tokens, so the depositor approves it rather than SecureFlow
+  const milestones = [hre.ethers.parseEther("100"), hre.ethers.parseEther("50")];
+  const total = milestones[0] + milestones[1];
+  await (await token.approve(permit2Address, hre.ethers.MaxUint256)).wait();
+
+  const latest = await hre.ethers.provider.getBlock("latest");
+  const deadline = BigInt(latest.timestamp) + 3600n;
+  const nonce = 0n;
+  const signature = "0x01"; // MockPermit2 accepts any non-empty signature
+
+  const depositorBefore = await token.balanceOf(depositor.address);
+  const contractBefore = await token.balanceOf(secureFlowAddress);
+  const escrowedBefore = await secureFlow.escrowedAmount(tokenAddress);
+
+  const args = [
+    beneficiary.address,
+    [],
+    1,
+    milestones,
+    ["Design", "Build"],
+    tokenAddress,
+    7n * 24n * 3600n,
+    "Permit2 deposit test",
+    "Escrow funded through a Permit2 signature transfer",
+    nonce,
+    deadline,
+    signature,
+  ];
+
+  console.log("\n💰 Creating escrow with Permit2...");
+  const escrowId = await secureFlow.createEscrowWithPermit2.staticCall(...args);
+  await (await secureFlow.createEscrowWithPermit2(...args)).wait();
+  console.log("Escrow ID:", escrowId.toString());
+
+  check(
+    depositorBefore - (await token.balanceOf(depositor.address)) === total,
+    "depositor paid the escrow total"
+  );
+  check(
+    (await token.balanceOf(secureFlowAddress)) - contractBefore === total,
+    "SecureFlow received the escrow total"
+  );
+  check(
+    (await secureFlow.escrowedAmount(tokenAddress)) - escrowedBefore === total,
+    "escrowedAmount tracks the deposit"
+  );
+  check(
+    (await permit2.nonceBitmap(depositor.address, 0)) & 1n,
+    "the permit nonce is spent"
+  );
+
+  let replayed = true;
+  try {
+    await secureFlow.createEscrowWithPermit2.staticCall(...args);
+  } catch {
+    replayed = false;
+  }
+  check(!replayed, "replaying the same permit reverts");
+
+  let expired = true;
+  try {
+    const stale = [...args];
+    stale[9] = nonce + 1n;
+    stale[10] = BigInt(latest.timestamp) - 1n;
+    await secureFlow.createEscrowWithPermit2.staticCall(...stale);
+  } catch {
+    expired = false;
+  }
+  check(!expired, "an expired permit reverts");
+}
+
+main()
+  .then(() => {
+    console.log("\n✅ Permit2 deposit test passed!");
+    process.exit(0);
+  })
+  .catch((error) => {
+    console.error("\n❌ Permit2 deposit test failed:", error);
+    process.exit(1);
+  });
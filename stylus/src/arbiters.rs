@@ -0,0 +1,443 @@
+//! Arbiter pool maintenance and panel assignment for SecureFlow
+//!
+//! Panels are drawn in two steps so nobody can pick the moment that yields a
+//! friendly draw. The action that needs a panel (a dispute, an appeal, a
+//! replacement) only records the next L2 block as the seed block. Once that
+//! block is mined anyone can run the draw, seeded with its hash. If the draw
+//! is left until the block falls out of ArbSys' 256-block window, it is
+//! rescheduled against a new block.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::{
+    call::{self, Call},
+    crypto,
+};
+use alloy_primitives::{address, Address, B256, U256};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::enumerable;
+
+// ArbSys precompile, exposes L2 block numbers and hashes to contracts.
+// On Arbitrum `block::number()` is an L1 estimate, so L2 heights come from here.
+const ARB_SYS: Address = address!("0000000000000000000000000000000000000064");
+const ARB_BLOCK_NUMBER_SELECTOR: [u8; 4] = [0xa3, 0xb1, 0xb3, 0x1d]; // arbBlockNumber()
+const ARB_BLOCK_HASH_SELECTOR: [u8; 4] = [0x2b, 0x40, 0x7a, 0x82]; // arbBlockHash(uint256)
+
+// First 32-byte word of a precompile's return data
+fn return_word(output: &[u8]) -> Result<[u8; 32], Vec<u8>> {
+    output
+        .get(..32)
+        .and_then(|word| word.try_into().ok())
+        .ok_or_else(|| Error::InvalidStatus(String::new()).into())
+}
+
+// Current L2 block number, `arb_sys` performs the static call to ArbSys
+pub fn arb_block_number(arb_sys: impl Fn(&[u8]) -> Result<Vec<u8>, Vec<u8>>) -> Result<U256, Vec<u8>> {
+    let output = arb_sys(&ARB_BLOCK_NUMBER_SELECTOR)?;
+    Ok(U256::from_be_bytes(return_word(&output)?))
+}
+
+// Hash of one of the last 256 L2 blocks, ArbSys reverts for any other number
+pub fn arb_block_hash(arb_sys: impl Fn(&[u8]) -> Result<Vec<u8>, Vec<u8>>, number: U256) -> Result<B256, Vec<u8>> {
+    let mut calldata = Vec::with_capacity(36);
+    calldata.extend_from_slice(&ARB_BLOCK_HASH_SELECTOR);
+    calldata.extend_from_slice(&number.to_be_bytes::<32>());
+    let output = arb_sys(&calldata)?;
+    Ok(B256::from(return_word(&output)?))
+}
+
+// arbBlockHash only serves the most recent 256 L2 blocks
+const BLOCK_HASH_WINDOW: u64 = 256;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DrawState {
+    Pending, // the seed block is not mined yet
+    Ready,
+    Expired, // the seed block's hash is no longer available
+}
+
+pub fn draw_state(seed_block: U256, current_block: U256) -> DrawState {
+    if current_block <= seed_block {
+        DrawState::Pending
+    } else if current_block - seed_block > U256::from(BLOCK_HASH_WINDOW) {
+        DrawState::Expired
+    } else {
+        DrawState::Ready
+    }
+}
+
+// Seed for a draw scheduled at `seed_block`, `key` tells apart draws for the
+// same escrow. None when the draw has expired and must be rescheduled.
+pub fn draw_seed(
+    arb_sys: impl Fn(&[u8]) -> Result<Vec<u8>, Vec<u8>> + Copy,
+    seed_block: U256,
+    escrow_id: U256,
+    key: U256,
+) -> Result<Option<B256>, Vec<u8>> {
+    match draw_state(seed_block, arb_block_number(arb_sys)?) {
+        DrawState::Pending => Err(Error::DrawNotReady(String::new()).into()),
+        DrawState::Expired => Ok(None),
+        DrawState::Ready => {
+            let mut preimage = arb_block_hash(arb_sys, seed_block)?.to_vec();
+            preimage.extend_from_slice(&escrow_id.to_be_bytes::<32>());
+            preimage.extend_from_slice(&key.to_be_bytes::<32>());
+            Ok(Some(crypto::keccak(preimage)))
+        }
+    }
+}
+
+fn call_arb_sys(calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    Ok(call::static_call(Call::new(), ARB_SYS, calldata)?)
+}
+
+// An auto-assigned panel is the smallest odd size `required` votes can be a majority of
+pub fn auto_panel_size(required_confirmations: usize) -> usize {
+    2 * required_confirmations - 1
+}
+
+// An appeal is heard by a strictly larger odd panel than the ruling it reviews
+pub fn appeal_panel_size(ruling_panel_len: usize) -> usize {
+    2 * ruling_panel_len + 1
+}
+
+// Pick `count` distinct addresses from a pool of `pool_len`, walking
+// keccak(seed, draw) indexes and keeping candidates `eligible` accepts. Gives up
+// with None after `pool_len + 8 * count` draws so a thin pool cannot run the call out of gas.
+pub fn pick_panel(
+    seed: B256,
+    count: usize,
+    pool_len: usize,
+    pool_at: impl Fn(usize) -> Address,
+    eligible: impl Fn(Address) -> bool,
+) -> Option<Vec<Address>> {
+    if pool_len == 0 {
+        return None;
+    }
+
+    let mut panel: Vec<Address> = Vec::new();
+    let max_draws = pool_len + 8 * count;
+    let mut draw = 0usize;
+    while panel.len() < count && draw < max_draws {
+        let mut preimage = seed.to_vec();
+        preimage.extend_from_slice(&U256::from(draw).to_be_bytes::<32>());
+        let index = U256::from_be_bytes(crypto::keccak(preimage).0) % U256::from(pool_len);
+        let candidate = pool_at(index.to::<usize>());
+        if !panel.contains(&candidate) && eligible(candidate) {
+            panel.push(candidate);
+        }
+        draw += 1;
+    }
+
+    (panel.len() == count).then_some(panel)
+}
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    pub fn add_to_arbiter_pool(&mut self, arbiter: Address) {
+        enumerable::insert(&mut self.arbiter_pool, &mut self.arbiter_pool_index, arbiter);
+    }
+
+    pub fn remove_from_arbiter_pool(&mut self, arbiter: Address) {
+        enumerable::remove(&mut self.arbiter_pool, &mut self.arbiter_pool_index, arbiter);
+    }
+
+    // Authorized and still holding the minimum stake, unstaking or slashing below it revokes the right to rule
+    pub fn is_staked_arbiter(&self, arbiter: Address) -> bool {
+        self.authorized_arbiters.get(arbiter)
+            && self.arbiter_info.get(arbiter).stake.get() >= self.min_arbiter_stake.get()
+    }
+
+    pub fn is_eligible_arbiter(&self, arbiter: Address, depositor: Address, beneficiary: Address) -> bool {
+        arbiter != depositor && arbiter != beneficiary && self.is_staked_arbiter(arbiter)
+    }
+
+    // Seed block for a draw requested now: the next L2 block, whose hash nobody knows yet
+    pub fn schedule_draw(&self) -> Result<U256, Vec<u8>> {
+        Ok(arb_block_number(call_arb_sys)? + U256::from(1))
+    }
+
+    pub fn panel_seed(&self, seed_block: U256, escrow_id: U256, key: U256) -> Result<Option<B256>, Vec<u8>> {
+        draw_seed(call_arb_sys, seed_block, escrow_id, key)
+    }
+
+    // Draw `count` distinct eligible arbiters from the pool, skipping anyone in `exclude`
+    pub fn draw_arbiters(
+        &self,
+        seed: B256,
+        count: usize,
+        depositor: Address,
+        beneficiary: Address,
+        exclude: &[Address],
+    ) -> Result<Vec<Address>, Vec<u8>> {
+        pick_panel(
+            seed,
+            count,
+            self.arbiter_pool.len(),
+            |index| self.arbiter_pool.get(index).unwrap_or_default(),
+            |candidate| !exclude.contains(&candidate) && self.is_eligible_arbiter(candidate, depositor, beneficiary),
+        )
+        .ok_or_else(|| Error::NotEnoughArbiters(String::new()).into())
+    }
+
+    pub fn escrow_arbiters(&self, escrow_id: U256) -> Vec<Address> {
+        let escrow = self.escrows.get(escrow_id);
+        (0..escrow.arbiters.len()).filter_map(|i| escrow.arbiters.get(i)).collect()
+    }
+
+    pub fn add_escrow_arbiter(&mut self, escrow_id: U256, arbiter: Address) {
+        self.escrows.setter(escrow_id).arbiters.push(arbiter);
+        self.escrow_arbiter_member.setter(escrow_id).setter(arbiter).set(true);
+        self.arbiter_escrows.setter(arbiter).push(escrow_id);
+    }
+
+    pub fn replace_escrow_arbiter(&mut self, escrow_id: U256, old_arbiter: Address, new_arbiter: Address) {
+        let mut escrow = self.escrows.setter(escrow_id);
+        for i in 0..escrow.arbiters.len() {
+            if escrow.arbiters.get(i) == Some(old_arbiter) {
+                if let Some(mut slot) = escrow.arbiters.setter(i) {
+                    slot.set(new_arbiter);
+                }
+                break;
+            }
+        }
+        drop(escrow);
+        let mut members = self.escrow_arbiter_member.setter(escrow_id);
+        members.setter(old_arbiter).set(false);
+        members.setter(new_arbiter).set(true);
+        drop(members);
+        self.arbiter_escrows.setter(new_arbiter).push(escrow_id);
+    }
+
+    // Escrows created without arbiters get a panel the first time they are disputed
+    pub fn needs_arbiter_panel(&self, escrow_id: U256) -> bool {
+        let escrow = self.escrows.get(escrow_id);
+        escrow.auto_arbiters.get() && escrow.arbiters.is_empty()
+    }
+
+    // The panel is the smallest odd size in which `required_confirmations` is a majority.
+    // Another dispute on the escrow may have drawn it already.
+    pub fn assign_arbiter_panel(&mut self, escrow_id: U256, seed: B256) -> Result<(), Vec<u8>> {
+        if !self.needs_arbiter_panel(escrow_id) {
+            return Ok(());
+        }
+        let escrow = self.escrows.get(escrow_id);
+        let panel_size = auto_panel_size(escrow.required_confirmations.get().to::<usize>());
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        drop(escrow);
+
+        let panel = self.draw_arbiters(seed, panel_size, depositor, beneficiary, &[])?;
+
+        for arbiter in panel {
+            self.add_escrow_arbiter(escrow_id, arbiter);
+        }
+
+        Ok(())
+    }
+
+    // An appeal goes to a larger panel drawn from outside the escrow's arbiters,
+    // and votes from the first round are cleared
+    pub fn assign_appeal_panel(&mut self, escrow_id: U256, milestone_index: U256, seed: B256) -> Result<(), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        drop(escrow);
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let appeal_panel_size = appeal_panel_size(dispute.ruling_panel.len());
+        // Earlier voters keep their vote records, so drawing one would leave a seat
+        // that can never vote. The ruling panel is out too so the appeal is heard fresh.
+        let mut prior_voters = Vec::new();
+        let mut i = 0;
+        while let Some(arbiter) = dispute.ruling_panel.get(i) {
+            prior_voters.push(arbiter);
+            i += 1;
+        }
+        let mut i = 0;
+        while let Some(voter) = dispute.voters.get(i) {
+            prior_voters.push(voter);
+            i += 1;
+        }
+        drop(dispute);
+        drop(disputes_map);
+        let mut excluded = self.escrow_arbiters(escrow_id);
+        excluded.extend(prior_voters);
+
+        let appeal_panel = self.draw_arbiters(seed, appeal_panel_size, depositor, beneficiary, &excluded)?;
+
+        let mut disputes_map = self.disputes.setter(escrow_id);
+        let mut dispute = disputes_map.setter(milestone_index);
+        dispute.voters.truncate(0);
+        for arbiter in appeal_panel {
+            dispute.appeal_panel.push(arbiter);
+            dispute.appeal_panel_member.setter(arbiter).set(true);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const L2_HEIGHT: u64 = 90_000_000;
+
+    // ArbSys stand-in: reports L2_HEIGHT and, like the precompile, only hashes
+    // the 256 L2 blocks before it
+    fn stub_arb_sys(calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        match <[u8; 4]>::try_from(&calldata[..4]).unwrap() {
+            ARB_BLOCK_NUMBER_SELECTOR => Ok(U256::from(L2_HEIGHT).to_be_bytes::<32>().to_vec()),
+            ARB_BLOCK_HASH_SELECTOR => {
+                let number = U256::from_be_slice(&calldata[4..36]);
+                let height = U256::from(L2_HEIGHT);
+                if number >= height || height - number > U256::from(256) {
+                    return Err(b"invalid block number".to_vec());
+                }
+                Ok(crypto::keccak(number.to_be_bytes::<32>()).to_vec())
+            }
+            _ => Err(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn reads_the_l2_block_number() {
+        assert_eq!(arb_block_number(stub_arb_sys).unwrap(), U256::from(L2_HEIGHT));
+    }
+
+    #[test]
+    fn draws_wait_for_the_seed_block_and_expire_with_its_hash() {
+        let seed_block = U256::from(100);
+        assert_eq!(draw_state(seed_block, U256::from(99)), DrawState::Pending);
+        assert_eq!(draw_state(seed_block, U256::from(100)), DrawState::Pending);
+        assert_eq!(draw_state(seed_block, U256::from(101)), DrawState::Ready);
+        assert_eq!(draw_state(seed_block, U256::from(356)), DrawState::Ready);
+        assert_eq!(draw_state(seed_block, U256::from(357)), DrawState::Expired);
+    }
+
+    #[test]
+    fn seeds_come_from_the_scheduled_l2_block() {
+        let seed_block = U256::from(L2_HEIGHT - 10);
+        let seed = draw_seed(stub_arb_sys, seed_block, U256::from(7), U256::ZERO).unwrap().unwrap();
+
+        let mut preimage = crypto::keccak(seed_block.to_be_bytes::<32>()).to_vec();
+        preimage.extend_from_slice(&U256::from(7).to_be_bytes::<32>());
+        preimage.extend_from_slice(&U256::ZERO.to_be_bytes::<32>());
+        assert_eq!(seed, crypto::keccak(preimage));
+
+        let other_key = draw_seed(stub_arb_sys, seed_block, U256::from(7), U256::from(1)).unwrap().unwrap();
+        assert_ne!(seed, other_key);
+    }
+
+    #[test]
+    fn seeds_are_unavailable_before_the_block_and_after_the_window() {
+        assert!(draw_seed(stub_arb_sys, U256::from(L2_HEIGHT), U256::ZERO, U256::ZERO).is_err());
+        assert_eq!(draw_seed(stub_arb_sys, U256::from(L2_HEIGHT - 257), U256::ZERO, U256::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn block_numbers_outside_the_window_revert() {
+        assert!(arb_block_hash(stub_arb_sys, U256::from(L2_HEIGHT)).is_err());
+        assert!(arb_block_hash(stub_arb_sys, U256::from(L2_HEIGHT - 257)).is_err());
+        // An L1 block number is far below the L2 window
+        assert!(arb_block_hash(stub_arb_sys, U256::from(20_000_000u64)).is_err());
+    }
+
+    fn pool(size: u8) -> Vec<Address> {
+        (1..=size).map(Address::repeat_byte).collect()
+    }
+
+    fn pick(seed: u8, count: usize, pool: &[Address], eligible: impl Fn(Address) -> bool) -> Option<Vec<Address>> {
+        pick_panel(B256::repeat_byte(seed), count, pool.len(), |index| pool[index], eligible)
+    }
+
+    #[test]
+    fn auto_panels_are_the_smallest_odd_size_for_the_quorum() {
+        assert_eq!(auto_panel_size(1), 1);
+        assert_eq!(auto_panel_size(2), 3);
+        assert_eq!(auto_panel_size(3), 5);
+    }
+
+    #[test]
+    fn panels_are_distinct_members_of_the_pool() {
+        let pool = pool(12);
+        for seed in 0..32 {
+            let panel = pick(seed, 5, &pool, |_| true).unwrap();
+            assert_eq!(panel.len(), 5);
+            for (i, arbiter) in panel.iter().enumerate() {
+                assert!(pool.contains(arbiter));
+                assert!(!panel[i + 1..].contains(arbiter));
+            }
+        }
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_panel() {
+        let pool = pool(12);
+        assert_eq!(pick(7, 3, &pool, |_| true), pick(7, 3, &pool, |_| true));
+        assert_ne!(pick(7, 3, &pool, |_| true), pick(8, 3, &pool, |_| true));
+    }
+
+    #[test]
+    fn ineligible_arbiters_are_never_drawn() {
+        let pool = pool(12);
+        let ineligible = pool[..6].to_vec();
+        for seed in 0..32 {
+            let panel = pick(seed, 3, &pool, |arbiter| !ineligible.contains(&arbiter)).unwrap();
+            assert!(panel.iter().all(|arbiter| !ineligible.contains(arbiter)));
+        }
+    }
+
+    #[test]
+    fn a_panel_can_take_every_eligible_arbiter() {
+        let pool = pool(5);
+        let mut panel = pick(3, 5, &pool, |_| true).unwrap();
+        panel.sort();
+        assert_eq!(panel, pool);
+    }
+
+    #[test]
+    fn a_thin_pool_fails_the_draw() {
+        assert_eq!(pick(1, 1, &[], |_| true), None);
+        assert_eq!(pick(1, 4, &pool(3), |_| true), None);
+        let pool = pool(6);
+        assert_eq!(pick(1, 3, &pool, |arbiter| arbiter == pool[0] || arbiter == pool[1]), None);
+    }
+
+    #[test]
+    fn appeal_panels_outnumber_the_ruling_panel() {
+        assert_eq!(appeal_panel_size(1), 3);
+        assert_eq!(appeal_panel_size(3), 7);
+        assert_eq!(appeal_panel_size(5), 11);
+    }
+
+    #[test]
+    fn appeals_never_draw_the_ruling_panel_or_earlier_voters() {
+        let pool = pool(16);
+        let escrow_arbiters = pool[..3].to_vec();
+        let ruling_panel = pool[3..5].to_vec();
+        // Voted in the first round, then was replaced off the escrow
+        let replaced_voter = pool[5];
+        let excluded = [escrow_arbiters, ruling_panel.clone(), [replaced_voter].to_vec()].concat();
+
+        for seed in 0..32 {
+            let panel = pick(seed, appeal_panel_size(ruling_panel.len()), &pool, |arbiter| !excluded.contains(&arbiter))
+                .unwrap();
+            assert!(panel.iter().all(|arbiter| !excluded.contains(arbiter)));
+        }
+    }
+
+    #[test]
+    fn an_appeal_fails_when_only_excluded_arbiters_remain() {
+        let pool = pool(8);
+        let excluded = pool[..4].to_vec();
+        assert_eq!(pick(1, appeal_panel_size(2), &pool, |arbiter| !excluded.contains(&arbiter)), None);
+    }
+
+    #[test]
+    fn short_return_data_is_an_error() {
+        assert!(arb_block_number(|_: &[u8]| Ok([0u8; 8].to_vec())).is_err());
+    }
+}
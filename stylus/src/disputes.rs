@@ -0,0 +1,270 @@
+//! Dispute voting and ruling execution for SecureFlow
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::block;
+use alloy_primitives::{Address, U256, U8};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowStatus, MilestoneStatus};
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    // Record an arbiter's vote. Returns the agreeing arbiters once `required`
+    // of them have voted for the same beneficiary amount.
+    pub fn record_arbiter_vote(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        arbiter: Address,
+        beneficiary_amount: U256,
+        required: usize,
+    ) -> Result<Option<Vec<Address>>, Vec<u8>> {
+        let mut disputes_map = self.disputes.setter(escrow_id);
+        let mut dispute = disputes_map.setter(milestone_index);
+        if dispute.voted.get(arbiter) {
+            return Err(Error::AlreadyVoted(String::new()).into());
+        }
+        dispute.voted.setter(arbiter).set(true);
+        dispute.vote_amounts.setter(arbiter).set(beneficiary_amount);
+        dispute.voters.push(arbiter);
+
+        // Votes from arbiters replaced, revoked or under-staked since voting no longer count
+        let appealed = dispute.appealed.get();
+        let mut agreeing = Vec::new();
+        let mut i = 0;
+        while let Some(voter) = dispute.voters.get(i) {
+            let is_member = if appealed {
+                dispute.appeal_panel_member.get(voter)
+            } else {
+                self.escrow_arbiter_member.get(escrow_id).get(voter)
+            };
+            if is_member && dispute.vote_amounts.get(voter) == beneficiary_amount {
+                agreeing.push(voter);
+            }
+            i += 1;
+        }
+        drop(dispute);
+        drop(disputes_map);
+        let panel: Vec<Address> = agreeing.into_iter().filter(|voter| self.is_staked_arbiter(*voter)).collect();
+
+        if panel.len() >= required.max(1) {
+            Ok(Some(panel))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Record a party's settlement offer, returns true once both parties offered the same split
+    pub fn record_settlement_offer(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        party: Address,
+        beneficiary_amount: U256,
+    ) -> bool {
+        let depositor = self.escrows.get(escrow_id).depositor.get();
+        let mut disputes_map = self.disputes.setter(escrow_id);
+        let mut dispute = disputes_map.setter(milestone_index);
+        if party == depositor {
+            dispute.depositor_offer.set(beneficiary_amount);
+            dispute.depositor_offered.set(true);
+        } else {
+            dispute.beneficiary_offer.set(beneficiary_amount);
+            dispute.beneficiary_offered.set(true);
+        }
+        dispute.depositor_offered.get()
+            && dispute.beneficiary_offered.get()
+            && dispute.depositor_offer.get() == dispute.beneficiary_offer.get()
+    }
+
+    // Apply a panel decision. First-round decisions wait out the appeal window
+    // when one is configured; appeal decisions are final.
+    pub fn conclude_panel_vote(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        beneficiary_amount: U256,
+        panel: Vec<Address>,
+    ) -> Result<(), Vec<u8>> {
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        if dispute.appealed.get() {
+            let upheld = dispute.ruled_amount.get() == beneficiary_amount;
+            let bond = dispute.appeal_bond.get();
+            let appellant = dispute.appellant.get();
+            let mut original_panel = Vec::new();
+            let mut i = 0;
+            while let Some(arbiter) = dispute.ruling_panel.get(i) {
+                original_panel.push(arbiter);
+                i += 1;
+            }
+            drop(dispute);
+            drop(disputes_map);
+            let token = self.milestone_token(escrow_id, milestone_index);
+
+            if upheld {
+                // The original panel earns the fee, the appeal panel keeps the bond
+                self.execute_ruling(escrow_id, milestone_index, beneficiary_amount, &original_panel)?;
+                self.record_cases_handled(&panel);
+                if bond > U256::ZERO {
+                    self.credit_arbitration_fee(token, bond, &panel);
+                }
+            } else {
+                self.execute_ruling(escrow_id, milestone_index, beneficiary_amount, &panel)?;
+                for arbiter in &original_panel {
+                    let mut info = self.arbiter_info.setter(*arbiter);
+                    let overturned = info.cases_overturned.get();
+                    info.cases_overturned.set(overturned + U256::from(1));
+                }
+                if bond > U256::ZERO {
+                    self.transfer_out(token, appellant, bond)?;
+                }
+            }
+            return Ok(());
+        }
+        drop(dispute);
+        drop(disputes_map);
+
+        if self.appeal_window.get() == U256::ZERO {
+            return self.execute_ruling(escrow_id, milestone_index, beneficiary_amount, &panel);
+        }
+
+        let mut disputes_map_mut = self.disputes.setter(escrow_id);
+        let mut dispute_mut = disputes_map_mut.setter(milestone_index);
+        dispute_mut.ruling_pending.set(true);
+        dispute_mut.ruled_amount.set(beneficiary_amount);
+        dispute_mut.ruled_at.set(U256::from(block::timestamp()));
+        for arbiter in panel {
+            dispute_mut.ruling_panel.push(arbiter);
+        }
+
+        Ok(())
+    }
+
+    // Return a posted appeal bond when the parties settle before the appeal concludes
+    pub fn release_appeal_bond(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let bond = dispute.appeal_bond.get();
+        let appellant = dispute.appellant.get();
+        drop(dispute);
+        drop(disputes_map);
+        if bond == U256::ZERO {
+            return Ok(());
+        }
+
+        self.disputes.setter(escrow_id).setter(milestone_index).appeal_bond.set(U256::ZERO);
+        let token = self.milestone_token(escrow_id, milestone_index);
+        self.transfer_out(token, appellant, bond)
+    }
+
+    pub fn record_cases_handled(&mut self, panel: &[Address]) {
+        for arbiter in panel {
+            let mut info = self.arbiter_info.setter(*arbiter);
+            let handled = info.cases_handled.get();
+            info.cases_handled.set(handled + U256::from(1));
+        }
+    }
+
+    // Fixed fee plus basis points of the disputed amount, capped at the amount
+    pub fn arbitration_fee_for(&self, escrow_id: U256, milestone_amount: U256) -> U256 {
+        let escrow = self.escrows.get(escrow_id);
+        let fee = escrow.arbitration_fee.get()
+            + milestone_amount * escrow.arbitration_fee_bps.get() / U256::from(10_000);
+        fee.min(milestone_amount)
+    }
+
+    // Settle a disputed milestone. When `panel` is non-empty the arbitration fee
+    // is taken out of the milestone, borne by each side in proportion to what the
+    // ruling awards it, and credited to the panel.
+    pub fn execute_ruling(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        beneficiary_amount: U256,
+        panel: &[Address],
+    ) -> Result<(), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let token = self.milestone_token(escrow_id, milestone_index);
+        let total = escrow.total_amount.get();
+        drop(escrow);
+
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        let milestone_amount = milestone.amount.get();
+        // Only what child escrows did not spend is left to split
+        let available = milestone_amount - milestone.subcontracted.get();
+        drop(milestone);
+        drop(milestones_map);
+        let fee = if panel.is_empty() {
+            U256::ZERO
+        } else {
+            self.arbitration_fee_for(escrow_id, milestone_amount).min(available)
+        };
+        let net = available - fee;
+        let beneficiary_payout = if milestone_amount == U256::ZERO {
+            U256::ZERO
+        } else {
+            beneficiary_amount * net / milestone_amount
+        };
+        let refund_payout = net - beneficiary_payout;
+
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Resolved as u8));
+        milestone_mut.approved_at.set(U256::from(block::timestamp()));
+        drop(milestone_mut);
+        drop(milestones_map_mut);
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        let paid = escrow_mut.paid_amount.get() + beneficiary_amount;
+        let refunded = escrow_mut.refunded_amount.get() + (milestone_amount - beneficiary_amount);
+        escrow_mut.paid_amount.set(paid);
+        escrow_mut.refunded_amount.set(refunded);
+        let open_disputes = escrow_mut.open_disputes.get();
+        escrow_mut.open_disputes.set(open_disputes - U256::from(1));
+        drop(escrow_mut);
+        if paid + refunded == total {
+            self.close_escrow(escrow_id, EscrowStatus::Released);
+        } else {
+            self.sync_escrow_status(escrow_id);
+        }
+
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed - available);
+        self.book_token_settlement(escrow_id, token, beneficiary_amount, milestone_amount - beneficiary_amount);
+
+        self.record_cases_handled(panel);
+
+        if fee > U256::ZERO {
+            self.credit_arbitration_fee(token, fee, panel);
+        }
+
+        if beneficiary_payout > U256::ZERO {
+            self.pay_beneficiary(escrow_id, milestone_index, token, beneficiary_payout)?;
+        }
+
+        if refund_payout > U256::ZERO {
+            self.refund_depositor(escrow_id, token, refund_payout)?;
+        }
+
+        Ok(())
+    }
+
+    // Split a fee evenly across arbiters, the first one takes the rounding dust
+    pub fn credit_arbitration_fee(&mut self, token: Address, fee: U256, arbiters: &[Address]) {
+        let held = self.arbitration_fees_held.get(token);
+        self.arbitration_fees_held.setter(token).set(held + fee);
+
+        let share = fee / U256::from(arbiters.len());
+        let dust = fee - share * U256::from(arbiters.len());
+        for (i, arbiter) in arbiters.iter().enumerate() {
+            let amount = if i == 0 { share + dust } else { share };
+            let mut balances = self.arbiter_fee_balances.setter(*arbiter);
+            let balance = balances.get(token);
+            balances.setter(token).set(balance + amount);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Enumerable address sets backed by a list and a 1-based position index
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use stylus_sdk::storage::{StorageAddress, StorageMap, StorageU256, StorageVec};
+use alloy_primitives::{Address, U256};
+
+// Returns false when the item is already present
+pub fn insert(list: &mut StorageVec<StorageAddress>, index: &mut StorageMap<Address, StorageU256>, item: Address) -> bool {
+    if index.get(item) != U256::ZERO {
+        return false;
+    }
+    list.push(item);
+    index.setter(item).set(U256::from(list.len()));
+    true
+}
+
+// Swap-and-pop removal, returns false when the item was not present
+pub fn remove(list: &mut StorageVec<StorageAddress>, index: &mut StorageMap<Address, StorageU256>, item: Address) -> bool {
+    let position = index.get(item);
+    if position == U256::ZERO {
+        return false;
+    }
+    let slot_index = position.to::<usize>() - 1;
+    let last_index = list.len() - 1;
+    if slot_index != last_index {
+        let last = list.get(last_index).unwrap_or_default();
+        if let Some(mut slot) = list.setter(slot_index) {
+            slot.set(last);
+        }
+        index.setter(last).set(position);
+    }
+    list.pop();
+    index.setter(item).set(U256::ZERO);
+    true
+}
+
+pub fn page(list: &StorageVec<StorageAddress>, offset: U256, limit: U256) -> Vec<Address> {
+    let count = U256::from(list.len());
+    let mut items = Vec::new();
+    let mut i = offset;
+    while i < count && i < offset.saturating_add(limit) {
+        if let Some(item) = list.get(i) {
+            items.push(item);
+        }
+        i += U256::from(1);
+    }
+    items
+}
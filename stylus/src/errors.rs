@@ -25,6 +25,42 @@ pub enum Error {
     NothingToRefund(String),
     DeadlineNotPassed(String),
     EmergencyPeriodNotReached(String),
+    MilestoneFrozen(String),
+    InvalidEvidence(String),
+    TooMuchEvidence(String),
+    ResponsePeriodActive(String),
+    AlreadyVoted(String),
+    NoFeesToClaim(String),
+    InsufficientStake(String),
+    CooldownActive(String),
+    NotEnoughArbiters(String),
+    ConflictOfInterest(String),
+    RulingPending(String),
+    AppealWindowOpen(String),
+    AppealWindowClosed(String),
+    BelowTokenMinimum(String),
+    AboveTokenMaximum(String),
+    TokenCapExceeded(String),
+    TokenDepositsPaused(String),
+    InvalidSignature(String),
+    SignatureExpired(String),
+    NoActiveEscrow(String),
+    SpendLimitExceeded(String),
+    SelectorNotSponsored(String),
+    InvalidEscrowType(String),
+    PeriodNotElapsed(String),
+    NothingToWithdraw(String),
+    OverBudget(String),
+    MilestoneNotFunded(String),
+    AlreadyFunded(String),
+    TokenCountMismatch(String),
+    InvalidSplit(String),
+    ChildEscrowsOpen(String),
+    AppealExpired(String),
+    SubcontractNotApproved(String),
+    ArbiterRevoked(String),
+    StakingDisabled(String),
+    DrawNotReady(String),
     // Specific errors for escrow creation
     BeneficiaryEqualsDepositor(String),
     EmptyMilestones(String),
@@ -56,6 +92,42 @@ impl From<Error> for Vec<u8> {
             Error::NothingToRefund(_) => b"NO_REFUND".to_vec(),
             Error::DeadlineNotPassed(_) => b"DEADLINE".to_vec(),
             Error::EmergencyPeriodNotReached(_) => b"EMERG_NR".to_vec(),
+            Error::MilestoneFrozen(_) => b"MS_FROZEN".to_vec(),
+            Error::InvalidEvidence(_) => b"INV_EVID".to_vec(),
+            Error::TooMuchEvidence(_) => b"TOO_EVID".to_vec(),
+            Error::ResponsePeriodActive(_) => b"RESP_ACTIVE".to_vec(),
+            Error::AlreadyVoted(_) => b"VOTED".to_vec(),
+            Error::NoFeesToClaim(_) => b"NO_FEES".to_vec(),
+            Error::InsufficientStake(_) => b"LOW_STAKE".to_vec(),
+            Error::CooldownActive(_) => b"COOLDOWN".to_vec(),
+            Error::NotEnoughArbiters(_) => b"NO_ARB".to_vec(),
+            Error::ConflictOfInterest(_) => b"CONFLICT".to_vec(),
+            Error::RulingPending(_) => b"RULED".to_vec(),
+            Error::AppealWindowOpen(_) => b"APPEAL_OPEN".to_vec(),
+            Error::AppealWindowClosed(_) => b"APPEAL_CLOSED".to_vec(),
+            Error::BelowTokenMinimum(_) => b"TOKEN_MIN".to_vec(),
+            Error::AboveTokenMaximum(_) => b"TOKEN_MAX".to_vec(),
+            Error::TokenCapExceeded(_) => b"TOKEN_CAP".to_vec(),
+            Error::TokenDepositsPaused(_) => b"TOKEN_PAUSED".to_vec(),
+            Error::InvalidSignature(_) => b"INV_SIG".to_vec(),
+            Error::SignatureExpired(_) => b"SIG_EXPIRED".to_vec(),
+            Error::NoActiveEscrow(_) => b"NO_ACTIVE".to_vec(),
+            Error::SpendLimitExceeded(_) => b"SPEND_LIMIT".to_vec(),
+            Error::SelectorNotSponsored(_) => b"NOT_SPONSORED".to_vec(),
+            Error::InvalidEscrowType(_) => b"INV_TYPE".to_vec(),
+            Error::PeriodNotElapsed(_) => b"NOT_DUE".to_vec(),
+            Error::NothingToWithdraw(_) => b"NO_WITHDRAW".to_vec(),
+            Error::OverBudget(_) => b"OVER_BUDGET".to_vec(),
+            Error::MilestoneNotFunded(_) => b"NOT_FUNDED".to_vec(),
+            Error::AlreadyFunded(_) => b"FUNDED".to_vec(),
+            Error::TokenCountMismatch(_) => b"TOKEN_COUNT".to_vec(),
+            Error::InvalidSplit(_) => b"INV_SPLIT".to_vec(),
+            Error::ChildEscrowsOpen(_) => b"CHILD_OPEN".to_vec(),
+            Error::AppealExpired(_) => b"APPEAL_EXPIRED".to_vec(),
+            Error::SubcontractNotApproved(_) => b"SUB_UNAPPROVED".to_vec(),
+            Error::ArbiterRevoked(_) => b"ARB_REVOKED".to_vec(),
+            Error::StakingDisabled(_) => b"STAKE_OFF".to_vec(),
+            Error::DrawNotReady(_) => b"DRAW_PENDING".to_vec(),
             // Specific errors for escrow creation
             Error::BeneficiaryEqualsDepositor(_) => b"BENEF_EQ_DEP".to_vec(),
             Error::EmptyMilestones(_) => b"EMPTY_MS".to_vec(),
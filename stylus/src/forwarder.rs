@@ -0,0 +1,70 @@
+//! ERC-2771 trusted forwarder support for SecureFlow
+//!
+//! The contract entrypoint strips the sender a trusted forwarder appends to
+//! calldata before routing, and `msg_sender` returns it in place of the
+//! forwarder for the rest of the call.
+
+extern crate alloc;
+use core::cell::UnsafeCell;
+
+use stylus_sdk::storage::TopLevelStorage;
+use alloy_primitives::Address;
+use crate::storage::SecureFlow;
+#[cfg(not(feature = "paymaster"))]
+use {
+    alloc::vec::Vec,
+    alloy_primitives::U256,
+    stylus_sdk::{abi::Router, msg, prelude::*, storage::StorageType, ArbResult},
+};
+
+// Stylus contracts run single-threaded, one call per instance
+struct SenderCell(UnsafeCell<Address>);
+
+unsafe impl Sync for SenderCell {}
+
+static CALL_SENDER: SenderCell = SenderCell(UnsafeCell::new(Address::ZERO));
+
+unsafe impl TopLevelStorage for SecureFlow {}
+
+const _: () = {
+    <SecureFlow>::__stylus_assert_overrides();
+};
+
+// The paymaster build exports its own entrypoint instead
+#[cfg(not(feature = "paymaster"))]
+#[entrypoint]
+fn user_main(input: Vec<u8>) -> ArbResult {
+    if input.len() < 4 {
+        return Err(Vec::new());
+    }
+    let mut storage = unsafe { <SecureFlow as StorageType>::new(U256::ZERO, 0) };
+
+    let forwarder = storage.trusted_forwarder.get();
+    let mut calldata = &input[..];
+    let mut sender = msg::sender();
+    if forwarder != Address::ZERO && sender == forwarder && input.len() >= 24 {
+        let (call, appended) = input.split_at(input.len() - 20);
+        calldata = call;
+        sender = Address::from_slice(appended);
+    }
+    unsafe { *CALL_SENDER.0.get() = sender };
+
+    let selector = u32::from_be_bytes([calldata[0], calldata[1], calldata[2], calldata[3]]);
+    match <SecureFlow as Router<SecureFlow>>::route(&mut storage, selector, &calldata[4..]) {
+        Some(result) => result,
+        None => Err(Vec::new()),
+    }
+}
+
+#[cfg(all(feature = "export-abi", not(feature = "paymaster")))]
+pub fn print_abi(license: &str, pragma: &str) {
+    stylus_sdk::abi::export::print_abi::<SecureFlow>(license, pragma);
+}
+
+impl SecureFlow {
+    // The caller on whose behalf this call runs: the address appended by the
+    // trusted forwarder when it relays, msg.sender otherwise
+    pub fn msg_sender(&self) -> Address {
+        unsafe { *CALL_SENDER.0.get() }
+    }
+}
@@ -3,11 +3,12 @@
 extern crate alloc;
 use alloc::{string::String, vec::Vec};
 
-use stylus_sdk::msg;
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, U256, U8};
 use crate::storage::SecureFlow;
 use crate::errors::Error;
+use crate::types::EscrowStatus;
 
+#[allow(clippy::drop_non_drop)]
 impl SecureFlow {
     // Initialize constants
     pub fn init_constants(&mut self) {
@@ -21,27 +22,166 @@ impl SecureFlow {
         self.reputation_per_milestone.set(U256::from(10));
         self.reputation_per_escrow.set(U256::from(25));
         self.min_rep_eligible_escrow_value.set(U256::from(10_000_000_000_000_000u64));
+        self.dispute_response_period.set(U256::from(259200)); // 3 days
+        self.max_evidence_per_dispute.set(U256::from(20));
+        self.max_evidence_note_length.set(U256::from(280));
+        self.unstake_cooldown.set(U256::from(1209600)); // 14 days
+        self.min_arbiter_stake.set(U256::from(100_000_000_000_000_000u64)); // 0.1 ETH
+        self.appeal_bond_bps.set(U256::from(1000)); // 10%, appeals stay off until a window is set
+        self.appeal_voting_period.set(U256::from(604800)); // 7 days
     }
     
     // Helper functions
     pub fn is_arbiter_for_escrow_internal(&self, escrow_id: U256, arbiter: Address) -> bool {
+        self.escrow_arbiter_member.get(escrow_id).get(arbiter)
+    }
+    
+    // Work can continue on undisputed milestones while others are in dispute
+    pub fn is_active_status(status: U8) -> bool {
+        status == EscrowStatus::InProgress as u8 || status == EscrowStatus::Disputed as u8
+    }
+
+    pub fn is_closed_status(status: U8) -> bool {
+        status == EscrowStatus::Released as u8
+            || status == EscrowStatus::Refunded as u8
+            || status == EscrowStatus::Expired as u8
+    }
+
+    // Upfront-funded escrows count every milestone as funded
+    pub fn is_milestone_funded(&self, escrow_id: U256, milestone_index: U256) -> bool {
+        !self.escrows.get(escrow_id).incremental.get()
+            || self.milestones.get(escrow_id).get(milestone_index).funded.get()
+    }
+
+    pub fn milestone_token(&self, escrow_id: U256, milestone_index: U256) -> Address {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.multi_token.get() {
+            self.milestones.get(escrow_id).get(milestone_index).token.get()
+        } else {
+            escrow.token.get()
+        }
+    }
+
+    pub fn escrow_token_list(&self, escrow_id: U256) -> Vec<Address> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.multi_token.get() {
+            (0..escrow.tokens.len()).filter_map(|i| escrow.tokens.get(i)).collect()
+        } else {
+            alloc::vec![escrow.token.get()]
+        }
+    }
+
+    // Per-token totals are only kept for multi-token escrows, the others read EscrowData
+    pub fn book_token_settlement(&mut self, escrow_id: U256, token: Address, paid: U256, refunded: U256) {
+        if !self.escrows.get(escrow_id).multi_token.get() {
+            return;
+        }
+        let mut totals_map = self.escrow_token_totals.setter(escrow_id);
+        let mut totals = totals_map.setter(token);
+        let new_paid = totals.paid.get() + paid;
+        let new_refunded = totals.refunded.get() + refunded;
+        totals.paid.set(new_paid);
+        totals.refunded.set(new_refunded);
+    }
+
+    // Compared per token so amounts in different tokens are never added up
+    pub fn meets_rep_threshold(&self, escrow_id: U256, token: Address) -> bool {
+        let total = if self.escrows.get(escrow_id).multi_token.get() {
+            self.escrow_token_totals.get(escrow_id).get(token).total.get()
+        } else {
+            self.escrows.get(escrow_id).total_amount.get()
+        };
+        total >= self.min_rep_eligible_escrow_value.get()
+    }
+
+    // Move an escrow into a terminal status
+    pub fn close_escrow(&mut self, escrow_id: U256, status: EscrowStatus) {
+        self.escrows.setter(escrow_id).status.set(U8::from(status as u8));
         let escrow = self.escrows.get(escrow_id);
-        let arbiters = &escrow.arbiters;
-        // Check if arbiter is in the list
-        let mut i = 0;
-        loop {
-            if let Some(addr) = arbiters.get(i) {
-                if addr == arbiter {
-                    return true;
-                }
-                i += 1;
-            } else {
-                break;
+        let depositor = escrow.depositor.get();
+        let beneficiary = if escrow.is_open_job.get() { None } else { Some(escrow.beneficiary.get()) };
+        drop(escrow);
+        self.untrack_open_escrow(depositor);
+        if let Some(beneficiary) = beneficiary {
+            self.untrack_open_escrow(beneficiary);
+        }
+        for token in self.escrow_token_list(escrow_id) {
+            self.untrack_live_escrow(token);
+        }
+        if self.escrows.get(escrow_id).is_child.get() {
+            self.settle_child(escrow_id);
+        }
+    }
+
+    pub fn track_open_escrow(&mut self, user: Address) {
+        let open = self.open_escrow_count.get(user);
+        self.open_escrow_count.setter(user).set(open + U256::from(1));
+    }
+
+    pub fn untrack_open_escrow(&mut self, user: Address) {
+        let open = self.open_escrow_count.get(user);
+        self.open_escrow_count.setter(user).set(open - U256::from(1));
+    }
+
+    // Derive an open escrow's status from its dispute counter
+    pub fn sync_escrow_status(&mut self, escrow_id: U256) {
+        let mut escrow = self.escrows.setter(escrow_id);
+        if !Self::is_active_status(escrow.status.get()) {
+            return;
+        }
+        let status = if escrow.open_disputes.get() > U256::ZERO {
+            EscrowStatus::Disputed
+        } else {
+            EscrowStatus::InProgress
+        };
+        escrow.status.set(U8::from(status as u8));
+    }
+
+    // Shared arbiter checks for escrow creation. An empty list asks for a panel
+    // to be drawn from the pool at dispute time (see `assign_arbiter_panel`).
+    pub fn validate_arbiters(
+        &self,
+        arbiters: &[Address],
+        required_confirmations: u8,
+        depositor: Address,
+        beneficiary: Address,
+    ) -> Result<(), Vec<u8>> {
+        let max_arbiters = self.max_arbiters.get();
+        let max_arbiters_count = if max_arbiters == U256::ZERO { 5usize } else { max_arbiters.as_limbs()[0] as usize };
+        if required_confirmations == 0 {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        if arbiters.is_empty() {
+            if 2 * required_confirmations as usize - 1 > max_arbiters_count {
+                return Err(Error::TooManyArbiters(String::new()).into());
+            }
+            return Ok(());
+        }
+
+        if arbiters.len() > max_arbiters_count {
+            return Err(Error::TooManyArbiters(String::new()).into());
+        }
+
+        if required_confirmations as usize > arbiters.len() {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        let min_stake = self.min_arbiter_stake.get();
+        for arbiter in arbiters {
+            if *arbiter == depositor || *arbiter == beneficiary {
+                return Err(Error::ConflictOfInterest(String::new()).into());
+            }
+            if !self.authorized_arbiters.get(*arbiter) {
+                return Err(Error::ArbiterNotAuthorized(String::new()).into());
+            }
+            if self.arbiter_info.get(*arbiter).stake.get() < min_stake {
+                return Err(Error::InsufficientStake(String::new()).into());
             }
         }
-        false
+        Ok(())
     }
-    
+
     pub fn update_reputation(&mut self, user: Address, points: U256) {
         if user != Address::ZERO {
             let current = self.reputation.get(user);
@@ -51,12 +191,20 @@ impl SecureFlow {
     
     // Access control
     pub fn only_owner(&self) -> Result<(), Vec<u8>> {
-        if msg::sender() != self.owner.get() {
+        if self.msg_sender() != self.owner.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
         Ok(())
     }
     
+    pub fn only_owner_or_governance(&self) -> Result<(), Vec<u8>> {
+        let sender = self.msg_sender();
+        if sender != self.owner.get() && (sender != self.governance.get() || sender == Address::ZERO) {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+        Ok(())
+    }
+
     pub fn when_not_paused(&self) -> Result<(), Vec<u8>> {
         if self.paused.get() {
             return Err(Error::Paused(String::new()).into());
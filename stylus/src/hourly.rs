@@ -0,0 +1,89 @@
+//! Hourly escrow helpers for SecureFlow
+//!
+//! An hourly escrow deposits a budget up front and starts with no milestones.
+//! Each time log the beneficiary submits is appended as a Submitted milestone
+//! worth `hours * hourly_rate`, so approval, disputes and rulings reuse the
+//! milestone flow. `logged_amount` tracks how much of the budget logs claim.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::block;
+use alloy_primitives::{Address, B256, U256, U8};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowStatus, MilestoneStatus};
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    pub fn remaining_hourly_budget(&self, escrow_id: U256) -> U256 {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.logs_closed.get() {
+            return U256::ZERO;
+        }
+        escrow.total_amount.get() - escrow.logged_amount.get()
+    }
+
+    // Append a time log as a submitted milestone, returns its index
+    pub fn append_time_log(&mut self, escrow_id: U256, hours: U256, description_hash: B256) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let amount = hours.saturating_mul(escrow.hourly_rate.get());
+        let log_index = escrow.milestone_count.get();
+        let logged = escrow.logged_amount.get();
+        drop(escrow);
+
+        if amount == U256::ZERO {
+            return Err(Error::ZeroMilestoneAmount(String::new()).into());
+        }
+        if amount > self.remaining_hourly_budget(escrow_id) {
+            return Err(Error::OverBudget(String::new()).into());
+        }
+
+        let mut milestones_map = self.milestones.setter(escrow_id);
+        let mut milestone = milestones_map.setter(log_index);
+        milestone.amount.set(amount);
+        milestone.status.set(U8::from(MilestoneStatus::Submitted as u8));
+        milestone.submitted_at.set(U256::from(block::timestamp()));
+        milestone.approved_at.set(U256::ZERO);
+        milestone.disputed_at.set(U256::ZERO);
+        milestone.disputed_by.set(Address::ZERO);
+        milestone.description.0.set_bytes([]);
+        milestone.dispute_reason.0.set_bytes([]);
+        milestone.funded.set(true);
+        drop(milestone);
+        drop(milestones_map);
+
+        let mut time_logs = self.time_logs.setter(escrow_id);
+        let mut log = time_logs.setter(log_index);
+        log.hours.set(hours);
+        log.description_hash.set(description_hash);
+        drop(log);
+        drop(time_logs);
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.milestone_count.set(log_index + U256::from(1));
+        escrow_mut.logged_amount.set(logged + amount);
+
+        Ok(log_index)
+    }
+
+    // Stop new logs and refund the part of the budget no log has claimed.
+    // Logs still pending keep their funds until approved, claimed or resolved.
+    pub fn close_hourly_internal(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+        let unlogged = self.remaining_hourly_budget(escrow_id);
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.logs_closed.set(true);
+        let token = escrow_mut.token.get();
+        let terminal_status = if escrow_mut.paid_amount.get() > U256::ZERO {
+            EscrowStatus::Released
+        } else {
+            EscrowStatus::Refunded
+        };
+        drop(escrow_mut);
+
+        if unlogged > U256::ZERO {
+            self.record_refund(escrow_id, token, unlogged, unlogged, terminal_status)?;
+        }
+        Ok(unlogged)
+    }
+}
@@ -2,6 +2,8 @@
 //! Stylus implementation for Arbitrum
 
 #![cfg_attr(not(feature = "export-abi"), no_std)]
+// The exported ABI for this many methods nests deeper than the default limit
+#![recursion_limit = "256"]
 
 extern crate alloc;
 
@@ -11,7 +13,21 @@ pub mod errors;
 pub mod storage;
 pub mod helpers;
 pub mod transfers;
+pub mod tokens;
+pub mod refunds;
+pub mod disputes;
+pub mod arbiters;
+pub mod retainers;
+pub mod streams;
+pub mod hourly;
+pub mod splits;
+pub mod subcontracts;
+pub mod enumerable;
+pub mod signatures;
+pub mod forwarder;
 pub mod public;
+#[cfg(feature = "paymaster")]
+pub mod paymaster;
 
 // Re-export main contract
 pub use storage::SecureFlow;
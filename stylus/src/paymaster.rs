@@ -0,0 +1,282 @@
+//! ERC-4337 paymaster that sponsors SecureFlow calls
+//!
+//! Built as its own contract with `--features paymaster`. It only sponsors
+//! user operations whose account calls `execute(SecureFlow, 0, data)` with an
+//! allowed SecureFlow selector, from accounts that have an active escrow, and
+//! caps the gas each account can spend. Validation reads SecureFlow's storage,
+//! so the paymaster must hold a stake in the EntryPoint.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_sol_types::{sol_data, SolType},
+    call::{self, Call},
+    contract,
+    msg,
+    prelude::*,
+};
+use alloy_primitives::{Address, FixedBytes, B256, U256};
+use crate::errors::Error;
+
+// Account selectors
+const EXECUTE_SELECTOR: [u8; 4] = [0xb6, 0x1d, 0x27, 0xf6]; // execute(address,uint256,bytes)
+
+// EntryPoint selectors
+const DEPOSIT_TO_SELECTOR: [u8; 4] = [0xb7, 0x60, 0xfa, 0xf9]; // depositTo(address)
+const WITHDRAW_TO_SELECTOR: [u8; 4] = [0x20, 0x5c, 0x28, 0x78]; // withdrawTo(address,uint256)
+const ADD_STAKE_SELECTOR: [u8; 4] = [0x03, 0x96, 0xcb, 0x60]; // addStake(uint32)
+const UNLOCK_STAKE_SELECTOR: [u8; 4] = [0xbb, 0x9f, 0xe6, 0xbf]; // unlockStake()
+const WITHDRAW_STAKE_SELECTOR: [u8; 4] = [0xc2, 0x3a, 0x5c, 0xea]; // withdrawStake(address)
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31]; // balanceOf(address)
+
+// SecureFlow selectors
+const HAS_ACTIVE_ESCROW_SELECTOR: [u8; 4] = [0xc6, 0xe0, 0x8e, 0xe2]; // hasActiveEscrow(address)
+
+// ERC-4337 v0.7 PackedUserOperation:
+// (sender, nonce, initCode, callData, accountGasLimits, preVerificationGas, gasFees, paymasterAndData, signature)
+pub type PackedUserOperation = (Address, U256, Bytes, Bytes, B256, U256, B256, Bytes, Bytes);
+
+type ExecuteParams = (sol_data::Address, sol_data::Uint<256>, sol_data::Bytes);
+
+sol_storage! {
+    #[entrypoint]
+    pub struct SecureFlowPaymaster {
+        address owner;
+        address entry_point;
+        address secureflow;
+        mapping(bytes4 => bool) sponsored_selectors;
+        // Spending limits are lifetime totals per account; the owner resets or
+        // raises them. ERC-4337 validation cannot read the block timestamp, so
+        // there are no rolling windows.
+        uint256 default_spend_limit;
+        mapping(address => uint256) spend_limits; // overrides the default when non-zero
+        mapping(address => uint256) spent;
+        uint256 total_sponsored;
+    }
+}
+
+fn address_word(addr: Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(addr.as_slice());
+    word
+}
+
+#[public]
+impl SecureFlowPaymaster {
+    pub fn init(&mut self, entry_point: Address, secureflow: Address, default_spend_limit: U256) -> Result<(), Vec<u8>> {
+        if self.owner.get() != Address::ZERO {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+        self.owner.set(msg::sender());
+        self.entry_point.set(entry_point);
+        self.secureflow.set(secureflow);
+        self.default_spend_limit.set(default_spend_limit);
+        Ok(())
+    }
+
+    // ===== ERC-4337 =====
+    pub fn validate_paymaster_user_op(
+        &mut self,
+        user_op: PackedUserOperation,
+        _user_op_hash: B256,
+        max_cost: U256,
+    ) -> Result<(Bytes, U256), Vec<u8>> {
+        self.only_entry_point()?;
+        let (sender, _, _, call_data, _, _, _, _, _) = user_op;
+
+        self.check_sponsored_call(&call_data)?;
+        if !self.has_active_escrow(sender) {
+            return Err(Error::NoActiveEscrow(String::new()).into());
+        }
+
+        // Reserve the worst case now, postOp settles it to the actual cost
+        let spent = self.spent.get(sender) + max_cost;
+        if spent > self.spend_limit_of(sender) {
+            return Err(Error::SpendLimitExceeded(String::new()).into());
+        }
+        self.spent.setter(sender).set(spent);
+
+        let mut context = Vec::with_capacity(52);
+        context.extend_from_slice(sender.as_slice());
+        context.extend_from_slice(&max_cost.to_be_bytes::<32>());
+        Ok((Bytes(context), U256::ZERO))
+    }
+
+    pub fn post_op(
+        &mut self,
+        _mode: u8,
+        context: Bytes,
+        actual_gas_cost: U256,
+        _actual_user_op_fee_per_gas: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.only_entry_point()?;
+        if context.len() != 52 {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        let sender = Address::from_slice(&context[..20]);
+        let max_cost = U256::from_be_slice(&context[20..]);
+
+        let spent = self.spent.get(sender);
+        self.spent.setter(sender).set(spent - max_cost + actual_gas_cost);
+        let total = self.total_sponsored.get();
+        self.total_sponsored.set(total + actual_gas_cost);
+        Ok(())
+    }
+
+    // ===== Deposit Management =====
+    #[payable]
+    pub fn deposit(&mut self) -> Result<(), Vec<u8>> {
+        let mut calldata = Vec::with_capacity(36);
+        calldata.extend_from_slice(&DEPOSIT_TO_SELECTOR);
+        calldata.extend_from_slice(&address_word(contract::address()));
+        call::call(Call::new().value(msg::value()), self.entry_point.get(), &calldata)?;
+        Ok(())
+    }
+
+    pub fn withdraw_to(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let mut calldata = Vec::with_capacity(68);
+        calldata.extend_from_slice(&WITHDRAW_TO_SELECTOR);
+        calldata.extend_from_slice(&address_word(to));
+        calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+        call::call(Call::new(), self.entry_point.get(), &calldata)?;
+        Ok(())
+    }
+
+    #[payable]
+    pub fn add_stake(&mut self, unstake_delay_sec: u32) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let mut calldata = Vec::with_capacity(36);
+        calldata.extend_from_slice(&ADD_STAKE_SELECTOR);
+        calldata.extend_from_slice(&U256::from(unstake_delay_sec).to_be_bytes::<32>());
+        call::call(Call::new().value(msg::value()), self.entry_point.get(), &calldata)?;
+        Ok(())
+    }
+
+    pub fn unlock_stake(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        call::call(Call::new(), self.entry_point.get(), &UNLOCK_STAKE_SELECTOR)?;
+        Ok(())
+    }
+
+    pub fn withdraw_stake(&mut self, to: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let mut calldata = Vec::with_capacity(36);
+        calldata.extend_from_slice(&WITHDRAW_STAKE_SELECTOR);
+        calldata.extend_from_slice(&address_word(to));
+        call::call(Call::new(), self.entry_point.get(), &calldata)?;
+        Ok(())
+    }
+
+    // ===== Configuration =====
+    pub fn set_sponsored_selector(&mut self, selector: FixedBytes<4>, sponsored: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.sponsored_selectors.setter(selector).set(sponsored);
+        Ok(())
+    }
+
+    pub fn set_default_spend_limit(&mut self, limit: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.default_spend_limit.set(limit);
+        Ok(())
+    }
+
+    pub fn set_spend_limit(&mut self, user: Address, limit: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.spend_limits.setter(user).set(limit);
+        Ok(())
+    }
+
+    pub fn reset_spent(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.spent.setter(user).set(U256::ZERO);
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.owner.set(new_owner);
+        Ok(())
+    }
+
+    // ===== Views =====
+    pub fn get_deposit(&self) -> Result<U256, Vec<u8>> {
+        let mut calldata = Vec::with_capacity(36);
+        calldata.extend_from_slice(&BALANCE_OF_SELECTOR);
+        calldata.extend_from_slice(&address_word(contract::address()));
+        let output = call::static_call(Call::new(), self.entry_point.get(), &calldata)?;
+        if output.len() < 32 {
+            return Ok(U256::ZERO);
+        }
+        Ok(U256::from_be_slice(&output[..32]))
+    }
+
+    pub fn is_sponsored_selector(&self, selector: FixedBytes<4>) -> Result<bool, Vec<u8>> {
+        Ok(self.sponsored_selectors.get(selector))
+    }
+
+    // Returns (limit, spent)
+    pub fn get_spending(&self, user: Address) -> Result<(U256, U256), Vec<u8>> {
+        Ok((self.spend_limit_of(user), self.spent.get(user)))
+    }
+
+    // Returns (owner, entry point, SecureFlow, total sponsored)
+    pub fn get_paymaster_config(&self) -> Result<(Address, Address, Address, U256), Vec<u8>> {
+        Ok((
+            self.owner.get(),
+            self.entry_point.get(),
+            self.secureflow.get(),
+            self.total_sponsored.get(),
+        ))
+    }
+}
+
+impl SecureFlowPaymaster {
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if msg::sender() != self.owner.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+        Ok(())
+    }
+
+    fn only_entry_point(&self) -> Result<(), Vec<u8>> {
+        if msg::sender() != self.entry_point.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+        Ok(())
+    }
+
+    fn spend_limit_of(&self, user: Address) -> U256 {
+        let limit = self.spend_limits.get(user);
+        if limit == U256::ZERO { self.default_spend_limit.get() } else { limit }
+    }
+
+    // The account must call execute(SecureFlow, 0, data) where data targets a sponsored selector
+    fn check_sponsored_call(&self, call_data: &[u8]) -> Result<(), Vec<u8>> {
+        if call_data.len() < 4 || call_data[..4] != EXECUTE_SELECTOR {
+            return Err(Error::SelectorNotSponsored(String::new()).into());
+        }
+        let (target, value, inner) = <ExecuteParams as SolType>::abi_decode_params(&call_data[4..], true)
+            .map_err(|_| Vec::<u8>::from(Error::SelectorNotSponsored(String::new())))?;
+        if target != self.secureflow.get() || value != U256::ZERO || inner.len() < 4 {
+            return Err(Error::SelectorNotSponsored(String::new()).into());
+        }
+        let selector = FixedBytes::<4>::from_slice(&inner[..4]);
+        if !self.sponsored_selectors.get(selector) {
+            return Err(Error::SelectorNotSponsored(String::new()).into());
+        }
+        Ok(())
+    }
+
+    fn has_active_escrow(&self, user: Address) -> bool {
+        let mut calldata = Vec::with_capacity(36);
+        calldata.extend_from_slice(&HAS_ACTIVE_ESCROW_SELECTOR);
+        calldata.extend_from_slice(&address_word(user));
+        match call::static_call(Call::new(), self.secureflow.get(), &calldata) {
+            Ok(output) => output.len() >= 32 && output[31] == 1,
+            Err(_) => false,
+        }
+    }
+}
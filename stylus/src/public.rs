@@ -2,30 +2,59 @@
 //! Stylus only allows ONE #[public] impl block per contract
 
 extern crate alloc;
-use alloc::{string::String, vec::Vec};
+use alloc::{string::String, vec, vec::Vec};
 
 use stylus_sdk::{
+    abi::{Bytes, Router},
     prelude::*,
     msg,
     block,
+    crypto,
 };
-use alloy_primitives::{Address, U256, U8};
+use alloy_primitives::{Address, B256, U256, U8};
 use crate::storage::SecureFlow;
 use crate::errors::Error;
-use crate::types::{EscrowStatus, MilestoneStatus};
+use crate::types::{EscrowStatus, EscrowType, Funding, MilestoneStatus, TokenStatus};
+use crate::enumerable;
+use crate::signatures;
 // Note: SecureFlow is accessed directly from storage, not through helpers/transfers
 
+// Storage guards are dropped explicitly to end borrows before re-entering `self`
+#[allow(clippy::drop_non_drop)]
 #[public]
 impl SecureFlow {
     // ===== Initialization =====
     pub fn init(&mut self) -> Result<(), Vec<u8>> {
         self.init_constants();
-        self.owner.set(msg::sender());
+        self.owner.set(self.msg_sender());
         self.next_escrow_id.set(U256::from(1));
         Ok(())
     }
 
+    // ===== Batching =====
+    // Run several calls against this contract in one transaction, as the same
+    // sender. Calls are dispatched in-process rather than through a real
+    // delegatecall, which the reentrancy guard would reject. Not payable, so
+    // batched payable calls see a zero msg.value. Any failing call reverts
+    // the whole batch with that call's error.
+    pub fn multicall(&mut self, data: Vec<Bytes>) -> Result<Vec<Bytes>, Vec<u8>> {
+        let mut results = Vec::with_capacity(data.len());
+        for call in data {
+            if call.len() < 4 {
+                return Err(Vec::new());
+            }
+            let selector = u32::from_be_bytes([call[0], call[1], call[2], call[3]]);
+            match <SecureFlow as Router<SecureFlow>>::route(self, selector, &call[4..]) {
+                Some(Ok(output)) => results.push(Bytes(output)),
+                Some(Err(error)) => return Err(error),
+                None => return Err(Vec::new()),
+            }
+        }
+        Ok(results)
+    }
+
     // ===== Escrow Management =====
+    #[allow(clippy::too_many_arguments)]
     pub fn create_escrow(
         &mut self,
         beneficiary: Address,
@@ -41,28 +70,10 @@ impl SecureFlow {
         self.when_not_paused()?;
         self.when_job_creation_not_paused()?;
         
-        if token != Address::ZERO && !self.whitelisted_tokens.get(token) {
-            return Err(Error::TokenNotWhitelisted(String::new()).into());
-        }
-
-        let max_arbiters = self.max_arbiters.get();
-        let max_arbiters_count = if max_arbiters == U256::ZERO { 5usize } else { max_arbiters.as_limbs()[0] as usize };
-        if arbiters.is_empty() || arbiters.len() > max_arbiters_count {
-            return Err(Error::TooManyArbiters(String::new()).into());
-        }
-
-        if required_confirmations == 0 || required_confirmations as usize > arbiters.len() {
-            return Err(Error::InvalidAmount(String::new()).into());
-        }
-
-        for arbiter in &arbiters {
-            if !self.authorized_arbiters.get(*arbiter) {
-                return Err(Error::ArbiterNotAuthorized(String::new()).into());
-            }
-        }
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
         self.create_escrow_internal(
-            msg::sender(),
+            self.msg_sender(),
             beneficiary,
             arbiters,
             required_confirmations,
@@ -72,59 +83,57 @@ impl SecureFlow {
             duration,
             project_title,
             project_description,
-            false,
+            Funding::Allowance,
         )
     }
 
-    #[payable]
-    pub fn create_escrow_native(
+    // Same as create_escrow, but approves the transfer with an EIP-2612 permit
+    // for the escrow total in the same transaction
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_escrow_with_permit(
         &mut self,
         beneficiary: Address,
         arbiters: Vec<Address>,
         required_confirmations: u8,
         milestone_amounts: Vec<U256>,
         milestone_descriptions: Vec<String>,
+        token: Address,
         duration: U256,
         project_title: String,
         project_description: String,
+        permit_deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
     ) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
         self.when_job_creation_not_paused()?;
 
-        let max_arbiters = self.max_arbiters.get();
-        let max_arbiters_count = if max_arbiters == U256::ZERO { 5usize } else { max_arbiters.as_limbs()[0] as usize };
-        if arbiters.is_empty() || arbiters.len() > max_arbiters_count {
-            return Err(Error::TooManyArbiters(String::new()).into());
-        }
-
-        if required_confirmations == 0 || required_confirmations as usize > arbiters.len() {
-            return Err(Error::InvalidAmount(String::new()).into());
-        }
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        for arbiter in &arbiters {
-            if !self.authorized_arbiters.get(*arbiter) {
-                return Err(Error::ArbiterNotAuthorized(String::new()).into());
-            }
-        }
+        let total_amount = milestone_amounts.iter().fold(U256::ZERO, |acc, amount| acc.saturating_add(*amount));
+        self.try_permit(token, self.msg_sender(), total_amount, permit_deadline, v, r, s);
 
         self.create_escrow_internal(
-            msg::sender(),
+            self.msg_sender(),
             beneficiary,
             arbiters,
             required_confirmations,
             milestone_amounts,
             milestone_descriptions,
-            Address::ZERO,
+            token,
             duration,
             project_title,
             project_description,
-            true,
+            Funding::Allowance,
         )
     }
 
-    fn create_escrow_internal(
+    // Same as create_escrow, but pulls the deposit through a Permit2 signature
+    // transfer so tokens without EIP-2612 can be deposited in one transaction
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_escrow_with_permit2(
         &mut self,
-        depositor: Address,
         beneficiary: Address,
         arbiters: Vec<Address>,
         required_confirmations: u8,
@@ -134,156 +143,224 @@ impl SecureFlow {
         duration: U256,
         project_title: String,
         project_description: String,
-        is_native: bool,
+        permit_nonce: U256,
+        permit_deadline: U256,
+        signature: Bytes,
     ) -> Result<U256, Vec<u8>> {
-        // Check if it's an open job (beneficiary is zero address)
-        let is_open_job = beneficiary == Address::ZERO;
-        
-        // Only check beneficiary != depositor for non-open jobs
-        // For open jobs, beneficiary is zero address which is allowed
-        if !is_open_job && beneficiary == depositor {
-            return Err(Error::BeneficiaryEqualsDepositor(String::new()).into());
-        }
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
 
-        let min_duration = self.min_duration.get();
-        let max_duration = self.max_duration.get();
-        // Defensive check: if constants are zero (uninitialized), use defaults
-        let min_dur = if min_duration == U256::ZERO { U256::from(3600) } else { min_duration };
-        let max_dur = if max_duration == U256::ZERO { U256::from(31536000) } else { max_duration };
-        if duration < min_dur || duration > max_dur {
-            return Err(Error::InvalidDuration(String::new()).into());
-        }
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        let max_milestones = self.max_milestones.get();
-        let max_milestones_count = if max_milestones == U256::ZERO { 20usize } else { max_milestones.as_limbs()[0] as usize };
-        if milestone_amounts.is_empty() {
-            return Err(Error::EmptyMilestones(String::new()).into());
-        }
-        if milestone_amounts.len() > max_milestones_count {
-            return Err(Error::TooManyMilestones(String::new()).into());
-        }
+        self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            milestone_amounts,
+            milestone_descriptions,
+            token,
+            duration,
+            project_title,
+            project_description,
+            Funding::Permit2 { nonce: permit_nonce, deadline: permit_deadline, signature: signature.0 },
+        )
+    }
 
-        if milestone_amounts.len() != milestone_descriptions.len() {
-            return Err(Error::MilestoneCountMismatch(String::new()).into());
-        }
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_escrow_native(
+        &mut self,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        milestone_amounts: Vec<U256>,
+        milestone_descriptions: Vec<String>,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
 
-        if project_title.is_empty() {
-            return Err(Error::EmptyProjectTitle(String::new()).into());
-        }
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        // is_open_job already determined above
-        let mut total_amount = U256::ZERO;
-        for amount in &milestone_amounts {
-            if *amount == U256::ZERO {
-                return Err(Error::ZeroMilestoneAmount(String::new()).into());
-            }
-            total_amount += *amount;
-        }
+        self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            milestone_amounts,
+            milestone_descriptions,
+            Address::ZERO,
+            duration,
+            project_title,
+            project_description,
+            Funding::Native,
+        )
+    }
 
-        if is_native {
-            let sent_value = msg::value();
-            if sent_value != total_amount {
-                return Err(Error::ValueMismatch(String::new()).into());
-            }
-            let current = self.escrowed_amount.get(Address::ZERO);
-            self.escrowed_amount.setter(Address::ZERO).set(current + total_amount);
-        } else {
-            self.transfer_in(token, depositor, total_amount)?;
-            let current = self.escrowed_amount.get(token);
-            self.escrowed_amount.setter(token).set(current + total_amount);
-        }
+    // Create an escrow whose milestones are paid in different tokens.
+    // Native milestones are covered by msg.value, the others are pulled with
+    // transferFrom, so the depositor approves each token's total first.
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_multi_token_escrow(
+        &mut self,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        milestone_amounts: Vec<U256>,
+        milestone_descriptions: Vec<String>,
+        milestone_tokens: Vec<Address>,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
 
-        let escrow_id = self.next_escrow_id.get();
-        self.next_escrow_id.set(escrow_id + U256::from(1));
-        let deadline = U256::from(block::timestamp()) + duration;
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        let mut escrow = self.escrows.setter(escrow_id);
-        escrow.depositor.set(depositor);
-        escrow.beneficiary.set(beneficiary);
-        // Set arbiters - for new escrows, vector should be empty
-        // Just push the arbiters directly
-        let arbiters_vec = &mut escrow.arbiters;
-        // For new escrows, the vector is empty, so we can just push
-        // If somehow it's not empty, we'll append (shouldn't happen for new escrows)
-        for arbiter in arbiters {
-            arbiters_vec.push(arbiter);
-        }
-        escrow.required_confirmations.set(U8::from(required_confirmations));
-        escrow.token.set(token);
-        escrow.total_amount.set(total_amount);
-        escrow.paid_amount.set(U256::ZERO);
-        escrow.deadline.set(deadline);
-        escrow.status.set(U8::from(EscrowStatus::Pending as u8));
-        escrow.work_started.set(false);
-        escrow.created_at.set(U256::from(block::timestamp()));
-        escrow.milestone_count.set(U256::from(milestone_amounts.len()));
-        escrow.is_open_job.set(is_open_job);
-        escrow.project_title.0.set_bytes(project_title.as_bytes());
-        escrow.project_description.0.set_bytes(project_description.as_bytes());
-        drop(escrow); // Drop escrow reference before accessing milestones
+        let token = milestone_tokens.first().copied().unwrap_or(Address::ZERO);
+        self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            milestone_amounts,
+            milestone_descriptions,
+            token,
+            duration,
+            project_title,
+            project_description,
+            Funding::MultiToken(milestone_tokens),
+        )
+    }
 
-        // Create milestones
-        let mut milestones_map = self.milestones.setter(escrow_id);
-        for i in 0..milestone_amounts.len() {
-            let amount = milestone_amounts[i];
-            let description = &milestone_descriptions[i];
-            let mut milestone = milestones_map.setter(U256::from(i));
-            milestone.amount.set(amount);
-            milestone.description.0.set_bytes(description.as_bytes());
-            milestone.status.set(U8::from(MilestoneStatus::NotStarted as u8));
-            milestone.submitted_at.set(U256::ZERO);
-            milestone.approved_at.set(U256::ZERO);
-            milestone.disputed_at.set(U256::ZERO);
-            milestone.disputed_by.set(Address::ZERO);
-            milestone.dispute_reason.0.set_bytes(&[]);
-        }
-        drop(milestones_map);
+    // The beneficiary of a parent escrow subcontracts part of a funded milestone,
+    // within the allowance the depositor set with approve_subcontract and ending
+    // by the parent's deadline. The child is paid out of that milestone's locked
+    // amount, with the caller as its depositor, and the parent milestone stays
+    // frozen until every child settles.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_child_escrow(
+        &mut self,
+        parent_id: U256,
+        milestone_index: U256,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        milestone_amounts: Vec<U256>,
+        milestone_descriptions: Vec<String>,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
 
-        // Add to user escrows
-        let mut user_escrows = self.user_escrows.setter(depositor);
-        user_escrows.push(escrow_id);
-        if !is_open_job {
-            let mut user_escrows_beneficiary = self.user_escrows.setter(beneficiary);
-            user_escrows_beneficiary.push(escrow_id);
+        let sender = self.msg_sender();
+        let child_total = milestone_amounts.iter().fold(U256::ZERO, |sum, amount| sum.saturating_add(*amount));
+        self.ensure_can_subcontract(sender, parent_id, milestone_index, child_total)?;
+
+        if U256::from(block::timestamp()).saturating_add(duration) > self.escrows.get(parent_id).deadline.get() {
+            return Err(Error::InvalidDuration(String::new()).into());
         }
 
-        Ok(escrow_id)
+        self.validate_arbiters(&arbiters, required_confirmations, sender, beneficiary)?;
+
+        let token = self.milestone_token(parent_id, milestone_index);
+        let child_id = self.create_escrow_internal(
+            sender,
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            milestone_amounts,
+            milestone_descriptions,
+            token,
+            duration,
+            project_title,
+            project_description,
+            Funding::Parent,
+        )?;
+        self.link_child_escrow(parent_id, milestone_index, child_id, child_total);
+        Ok(child_id)
     }
 
-    // ===== Work Lifecycle =====
-    pub fn start_work(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+    // The parent depositor caps how much of a milestone its beneficiary may
+    // subcontract. The allowance can be lowered, but not below what is already
+    // subcontracted.
+    pub fn approve_subcontract(&mut self, escrow_id: U256, milestone_index: U256, amount: U256) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
-        
+
         let escrow = self.escrows.get(escrow_id);
         if escrow.depositor.get() == Address::ZERO {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.beneficiary.get() {
+        if self.msg_sender() != escrow.depositor.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::Pending as u8 {
+        if Self::is_closed_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        if escrow.work_started.get() {
-            return Err(Error::WorkNotStarted(String::new()).into());
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
         }
+        drop(escrow);
 
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.work_started.set(true);
-        escrow_mut.status.set(U8::from(EscrowStatus::InProgress as u8));
+        let mut milestones_map = self.milestones.setter(escrow_id);
+        let mut milestone = milestones_map.setter(milestone_index);
+        if amount > milestone.amount.get() || amount < milestone.subcontracted.get() {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        milestone.subcontract_allowance.set(amount);
 
         Ok(())
     }
 
-    pub fn submit_milestone(
+    // Create an escrow without a deposit. Each milestone is funded with
+    // fund_milestone and can only be submitted once funded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_incremental_escrow(
         &mut self,
-        escrow_id: U256,
-        milestone_index: U256,
-        description: String,
-    ) -> Result<(), Vec<u8>> {
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        milestone_amounts: Vec<U256>,
+        milestone_descriptions: Vec<String>,
+        token: Address,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
+
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
+
+        self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            milestone_amounts,
+            milestone_descriptions,
+            token,
+            duration,
+            project_title,
+            project_description,
+            Funding::Deferred,
+        )
+    }
+
+    // Deposit one milestone of an incremental escrow. Native escrows send
+    // exactly the milestone amount as msg.value.
+    #[payable]
+    pub fn fund_milestone(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -291,114 +368,131 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.beneficiary.get() {
+        if !escrow.incremental.get() {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        let depositor = escrow.depositor.get();
+        if self.msg_sender() != depositor {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::InProgress as u8 {
+        if Self::is_closed_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
         if milestone_index >= escrow.milestone_count.get() {
             return Err(Error::MilestoneNotFound(String::new()).into());
         }
+        let funded_amount = escrow.funded_amount.get();
+        drop(escrow);
+        let token = self.milestone_token(escrow_id, milestone_index);
 
         let milestones_map = self.milestones.get(escrow_id);
         let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::NotStarted as u8 {
-            return Err(Error::AlreadySubmitted(String::new()).into());
+        if milestone.funded.get() {
+            return Err(Error::AlreadyFunded(String::new()).into());
         }
+        if milestone.status.get() != MilestoneStatus::NotStarted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        let amount = milestone.amount.get();
+        drop(milestone);
+        drop(milestones_map);
 
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Submitted as u8));
-        milestone_mut.submitted_at.set(U256::from(block::timestamp()));
-        if !description.is_empty() {
-            milestone_mut.description.0.set_bytes(description.as_bytes());
+        self.ensure_accepts_deposits(token)?;
+        self.enforce_token_cap(token, amount)?;
+
+        if token == Address::ZERO {
+            if msg::value() != amount {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+        } else {
+            if msg::value() != U256::ZERO {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            self.transfer_in(token, depositor, amount)?;
         }
 
+        self.milestones.setter(escrow_id).setter(milestone_index).funded.set(true);
+        self.escrows.setter(escrow_id).funded_amount.set(funded_amount + amount);
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed + amount);
+
         Ok(())
     }
 
-    pub fn approve_milestone(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+    // Fund `period_count` periods of `period_amount` each. Native retainers send
+    // the total as msg.value, token retainers pull it with transferFrom.
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_retainer_escrow(
+        &mut self,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        token: Address,
+        period_amount: U256,
+        period_count: U256,
+        period_interval: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
 
-        let escrow = self.escrows.get(escrow_id);
-        if escrow.depositor.get() == Address::ZERO {
-            return Err(Error::InvalidEscrow(String::new()).into());
-        }
-
-        if msg::sender() != escrow.depositor.get() {
-            return Err(Error::Unauthorized(String::new()).into());
+        // Retainers need a known beneficiary, open jobs are milestone-only
+        if beneficiary == Address::ZERO {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::InProgress as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        let max_milestones = self.max_milestones.get();
+        let max_periods = if max_milestones == U256::ZERO { U256::from(20) } else { max_milestones };
+        if period_count == U256::ZERO {
+            return Err(Error::EmptyMilestones(String::new()).into());
         }
-
-        if milestone_index >= escrow.milestone_count.get() {
-            return Err(Error::MilestoneNotFound(String::new()).into());
+        if period_count > max_periods {
+            return Err(Error::TooManyMilestones(String::new()).into());
         }
-
-        let milestones_map = self.milestones.get(escrow_id);
-        let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::Submitted as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        if period_interval == U256::ZERO {
+            return Err(Error::InvalidDuration(String::new()).into());
         }
 
-        let amount = milestone.amount.get();
-        let token = escrow.token.get();
-        let beneficiary = escrow.beneficiary.get();
-        let depositor = escrow.depositor.get();
-        let total = escrow.total_amount.get();
-        let min_rep_value = self.min_rep_eligible_escrow_value.get();
-        let paid = escrow.paid_amount.get();
-        let new_paid = paid + amount;
-        
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Approved as u8));
-        milestone_mut.approved_at.set(U256::from(block::timestamp()));
-        drop(milestone_mut);
-        drop(milestones_map_mut);
+        let funding = if token == Address::ZERO {
+            Funding::Native
+        } else {
+            if msg::value() != U256::ZERO {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            Funding::Allowance
+        };
 
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.paid_amount.set(new_paid);
-        let escrowed = self.escrowed_amount.get(token);
-        self.escrowed_amount.setter(token).set(escrowed - amount);
-        drop(escrow_mut);
-        
-        self.transfer_out(token, beneficiary, amount)?;
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        if total >= min_rep_value {
-            let rep_points = self.reputation_per_milestone.get();
-            self.update_reputation(beneficiary, rep_points);
-        }
+        let count = period_count.to::<usize>();
+        let escrow_id = self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            vec![period_amount; count],
+            vec![String::new(); count],
+            token,
+            period_interval.saturating_mul(period_count),
+            project_title,
+            project_description,
+            funding,
+        )?;
 
-        if new_paid == total {
-            let mut escrow_mut = self.escrows.setter(escrow_id);
-            escrow_mut.status.set(U8::from(EscrowStatus::Released as u8));
-            drop(escrow_mut);
-            if total >= min_rep_value {
-                let rep_points = self.reputation_per_escrow.get();
-                self.update_reputation(beneficiary, rep_points);
-                self.update_reputation(depositor, rep_points);
-            }
-            let completed_beneficiary = self.completed_escrows.get(beneficiary);
-            self.completed_escrows.setter(beneficiary).set(completed_beneficiary + U256::from(1));
-            let completed_depositor = self.completed_escrows.get(depositor);
-            self.completed_escrows.setter(depositor).set(completed_depositor + U256::from(1));
-        }
+        let mut escrow = self.escrows.setter(escrow_id);
+        escrow.escrow_type.set(U8::from(EscrowType::Retainer as u8));
+        escrow.period_interval.set(period_interval);
 
-        Ok(())
+        Ok(escrow_id)
     }
 
-    pub fn reject_milestone(
-        &mut self,
-        escrow_id: U256,
-        milestone_index: U256,
-        reason: String,
-    ) -> Result<(), Vec<u8>> {
+    // The beneficiary collects a period once its interval has elapsed
+    pub fn claim_retainer_period(&mut self, escrow_id: U256, period_index: U256) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -406,40 +500,37 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
+        if escrow.escrow_type.get() != EscrowType::Retainer as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.beneficiary.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::InProgress as u8 {
+        if !Self::is_active_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        if milestone_index >= escrow.milestone_count.get() {
+        if period_index >= escrow.milestone_count.get() {
             return Err(Error::MilestoneNotFound(String::new()).into());
         }
+        drop(escrow);
 
-        let milestones_map = self.milestones.get(escrow_id);
-        let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::Submitted as u8 {
+        if self.milestones.get(escrow_id).get(period_index).status.get() != MilestoneStatus::NotStarted as u8 {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Rejected as u8));
-        milestone_mut.disputed_at.set(U256::from(block::timestamp()));
-        milestone_mut.disputed_by.set(msg::sender());
-        milestone_mut.dispute_reason.0.set_bytes(reason.as_bytes());
+        if !self.is_period_elapsed(escrow_id, period_index) {
+            return Err(Error::PeriodNotElapsed(String::new()).into());
+        }
 
-        Ok(())
+        self.release_milestone(escrow_id, period_index)
     }
 
-    pub fn resubmit_milestone(
-        &mut self,
-        escrow_id: U256,
-        milestone_index: U256,
-        description: String,
-    ) -> Result<(), Vec<u8>> {
+    // The depositor stops the retainer. Periods that have not elapsed are
+    // refunded; elapsed ones stay claimable and disputed ones await a ruling.
+    pub fn cancel_retainer(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -447,41 +538,90 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.beneficiary.get() {
+        if escrow.escrow_type.get() != EscrowType::Retainer as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::InProgress as u8 {
+        if Self::is_closed_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
+        drop(escrow);
 
-        if milestone_index >= escrow.milestone_count.get() {
-            return Err(Error::MilestoneNotFound(String::new()).into());
-        }
+        self.refund_future_periods(escrow_id, EscrowStatus::Refunded)?;
 
-        let milestones_map = self.milestones.get(escrow_id);
-        let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::Rejected as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        Ok(())
+    }
+
+    // Vest `amount` linearly from `start_time` to `end_time`. The stream runs
+    // without start_work; native streams send the amount as msg.value.
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_stream_escrow(
+        &mut self,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        token: Address,
+        amount: U256,
+        start_time: U256,
+        end_time: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
+
+        if beneficiary == Address::ZERO {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Submitted as u8));
-        milestone_mut.submitted_at.set(U256::from(block::timestamp()));
-        if !description.is_empty() {
-            milestone_mut.description.0.set_bytes(description.as_bytes());
+        let now = U256::from(block::timestamp());
+        if start_time < now || end_time <= start_time {
+            return Err(Error::InvalidDuration(String::new()).into());
         }
 
-        Ok(())
+        let funding = if token == Address::ZERO {
+            Funding::Native
+        } else {
+            if msg::value() != U256::ZERO {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            Funding::Allowance
+        };
+
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
+
+        let escrow_id = self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            vec![amount],
+            vec![String::new()],
+            token,
+            end_time - now,
+            project_title,
+            project_description,
+            funding,
+        )?;
+
+        let mut escrow = self.escrows.setter(escrow_id);
+        escrow.escrow_type.set(U8::from(EscrowType::Stream as u8));
+        escrow.stream_start.set(start_time);
+        escrow.stream_end.set(end_time);
+        escrow.work_started.set(true);
+        escrow.started_at.set(start_time);
+        escrow.status.set(U8::from(EscrowStatus::InProgress as u8));
+
+        Ok(escrow_id)
     }
 
-    pub fn dispute_milestone(
-        &mut self,
-        escrow_id: U256,
-        milestone_index: U256,
-        reason: String,
-    ) -> Result<(), Vec<u8>> {
+    // The beneficiary withdraws everything vested so far
+    pub fn withdraw_streamed(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -489,40 +629,25 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
-            return Err(Error::Unauthorized(String::new()).into());
+        if escrow.escrow_type.get() != EscrowType::Stream as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        let milestones_map = self.milestones.get(escrow_id);
-        let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::Submitted as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        if self.msg_sender() != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
         }
 
-        let dispute_period = self.dispute_period.get();
-        if U256::from(block::timestamp()) > milestone.submitted_at.get() + dispute_period {
-            return Err(Error::DisputePeriodExpired(String::new()).into());
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
         }
+        drop(escrow);
 
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Disputed as u8));
-        milestone_mut.disputed_at.set(U256::from(block::timestamp()));
-        milestone_mut.disputed_by.set(msg::sender());
-        milestone_mut.dispute_reason.0.set_bytes(reason.as_bytes());
-
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.status.set(U8::from(EscrowStatus::Disputed as u8));
-
-        Ok(())
+        self.withdraw_streamed_internal(escrow_id)
     }
 
-    pub fn resolve_dispute(
-        &mut self,
-        escrow_id: U256,
-        milestone_index: U256,
-        beneficiary_amount: U256,
-    ) -> Result<(), Vec<u8>> {
+    // The depositor stops the stream and reclaims the unvested part.
+    // Whatever vested before cancellation stays withdrawable.
+    pub fn cancel_stream(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -530,79 +655,80 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        let sender = msg::sender();
-        if sender != escrow.depositor.get() 
-            && sender != escrow.beneficiary.get() 
-            && !self.is_arbiter_for_escrow_internal(escrow_id, sender) {
+        if escrow.escrow_type.get() != EscrowType::Stream as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::Disputed as u8 {
+        if Self::is_closed_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
+        drop(escrow);
 
-        let milestones_map = self.milestones.get(escrow_id);
-        let milestone = milestones_map.get(milestone_index);
-        if milestone.status.get() != MilestoneStatus::Disputed as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        self.cancel_stream_internal(escrow_id)
+    }
+
+    // Deposit a budget billed at `hourly_rate` per logged hour. Logs start
+    // once the beneficiary calls start_work; native budgets are sent as msg.value.
+    #[allow(clippy::too_many_arguments)]
+    #[payable]
+    pub fn create_hourly_escrow(
+        &mut self,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        token: Address,
+        hourly_rate: U256,
+        budget: U256,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+    ) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+        self.when_job_creation_not_paused()?;
+
+        if beneficiary == Address::ZERO {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        let milestone_amount = milestone.amount.get();
-        if beneficiary_amount > milestone_amount {
+        if hourly_rate == U256::ZERO || hourly_rate > budget {
             return Err(Error::InvalidAmount(String::new()).into());
         }
 
-        let refund_amount = milestone_amount - beneficiary_amount;
-        let token = escrow.token.get();
-        let beneficiary = escrow.beneficiary.get();
-        let depositor = escrow.depositor.get();
-        let total = escrow.total_amount.get();
-        
-        let mut milestones_map_mut = self.milestones.setter(escrow_id);
-        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
-        milestone_mut.status.set(U8::from(MilestoneStatus::Resolved as u8));
-        milestone_mut.approved_at.set(U256::from(block::timestamp()));
-        drop(milestone_mut);
-        drop(milestones_map_mut);
-
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        let paid = escrow_mut.paid_amount.get();
-        
-        if beneficiary_amount > U256::ZERO {
-            let new_paid = paid + beneficiary_amount;
-            escrow_mut.paid_amount.set(new_paid);
-            let escrowed = self.escrowed_amount.get(token);
-            self.escrowed_amount.setter(token).set(escrowed - beneficiary_amount);
-            drop(escrow_mut);
-            self.transfer_out(token, beneficiary, beneficiary_amount)?;
-            escrow_mut = self.escrows.setter(escrow_id);
+        if token != Address::ZERO && msg::value() != U256::ZERO {
+            return Err(Error::ValueMismatch(String::new()).into());
         }
 
-        if refund_amount > U256::ZERO {
-            let escrowed = self.escrowed_amount.get(token);
-            self.escrowed_amount.setter(token).set(escrowed - refund_amount);
-            drop(escrow_mut);
-            self.transfer_out(token, depositor, refund_amount)?;
-            escrow_mut = self.escrows.setter(escrow_id);
-        }
+        self.validate_arbiters(&arbiters, required_confirmations, self.msg_sender(), beneficiary)?;
 
-        escrow_mut.status.set(U8::from(EscrowStatus::InProgress as u8));
+        let escrow_id = self.create_escrow_internal(
+            self.msg_sender(),
+            beneficiary,
+            arbiters,
+            required_confirmations,
+            Vec::new(),
+            Vec::new(),
+            token,
+            duration,
+            project_title,
+            project_description,
+            Funding::Hourly { budget },
+        )?;
 
-        let final_paid = escrow_mut.paid_amount.get();
-        if final_paid == total {
-            escrow_mut.status.set(U8::from(EscrowStatus::Released as u8));
-        }
+        let mut escrow = self.escrows.setter(escrow_id);
+        escrow.escrow_type.set(U8::from(EscrowType::Hourly as u8));
+        escrow.hourly_rate.set(hourly_rate);
+        drop(escrow);
 
-        Ok(())
+        Ok(escrow_id)
     }
 
-    // ===== Marketplace =====
-    pub fn apply_to_job(
-        &mut self,
-        escrow_id: U256,
-        cover_letter: String,
-        proposed_timeline: U256,
-    ) -> Result<(), Vec<u8>> {
+    // The beneficiary bills `hours` of work. The log is paid on approval, or
+    // claimable once the dispute period passes without a dispute.
+    pub fn submit_time_log(&mut self, escrow_id: U256, hours: U256, description_hash: B256) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -610,45 +736,65 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if !escrow.is_open_job.get() {
-            return Err(Error::InvalidStatus(String::new()).into());
+        if escrow.escrow_type.get() != EscrowType::Hourly as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::Pending as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        if self.msg_sender() != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if self.has_applied.get(escrow_id).get(msg::sender()) {
-            return Err(Error::AlreadySubmitted(String::new()).into());
+        if !Self::is_active_status(escrow.status.get()) || escrow.logs_closed.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
         }
+        drop(escrow);
 
-        let max_apps = self.max_applications.get();
-        let mut applications = self.escrow_applications.setter(escrow_id);
-        if U256::from(applications.len()) >= max_apps {
-            return Err(Error::TooManyMilestones(String::new()).into());
+        self.append_time_log(escrow_id, hours, description_hash)
+    }
+
+    pub fn claim_time_log(&mut self, escrow_id: U256, log_index: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if escrow.escrow_type.get() != EscrowType::Hourly as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        if msg::sender() == escrow.depositor.get() {
+        if self.msg_sender() != escrow.beneficiary.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if cover_letter.is_empty() {
-            return Err(Error::InvalidAmount(String::new()).into());
+        if !Self::is_active_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
         }
 
-          // Create application using grow() to get a mutable accessor
-          let mut app = applications.grow();
-          app.freelancer.set(msg::sender());
-          app.cover_letter.0.set_bytes(cover_letter.as_bytes());
-          app.proposed_timeline.set(proposed_timeline);
-          app.applied_at.set(U256::from(block::timestamp()));
-          app.exists.set(true);
-        self.has_applied.setter(escrow_id).setter(msg::sender()).set(true);
+        if log_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+        drop(escrow);
 
-        Ok(())
+        let milestones_map = self.milestones.get(escrow_id);
+        let log = milestones_map.get(log_index);
+        if log.status.get() != MilestoneStatus::Submitted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if U256::from(block::timestamp()) <= log.submitted_at.get() + self.dispute_period.get() {
+            return Err(Error::PeriodNotElapsed(String::new()).into());
+        }
+        drop(log);
+        drop(milestones_map);
+
+        self.release_milestone(escrow_id, log_index)
     }
 
-    pub fn accept_freelancer(&mut self, escrow_id: U256, freelancer: Address) -> Result<(), Vec<u8>> {
+    // Either party ends the engagement. The unlogged budget goes back to the
+    // depositor and no further logs can be submitted.
+    pub fn close_hourly_escrow(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -656,34 +802,172 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
+        if escrow.escrow_type.get() != EscrowType::Hourly as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        let sender = self.msg_sender();
+        if sender != escrow.depositor.get() && sender != escrow.beneficiary.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if !escrow.is_open_job.get() {
+        if Self::is_closed_status(escrow.status.get()) || escrow.logs_closed.get() {
             return Err(Error::InvalidStatus(String::new()).into());
         }
+        drop(escrow);
 
-        if escrow.status.get() != EscrowStatus::Pending as u8 {
-            return Err(Error::InvalidStatus(String::new()).into());
+        self.close_hourly_internal(escrow_id)
+    }
+
+    // ===== Work Lifecycle =====
+    pub fn start_work(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+        self.start_work_internal(self.msg_sender(), escrow_id)
+    }
+
+    pub fn start_work_by_sig(
+        &mut self,
+        signer: Address,
+        escrow_id: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        let struct_hash = signatures::hash_struct(
+            signatures::START_WORK_TYPE,
+            &[signatures::word(escrow_id), signatures::word(self.nonces.get(signer)), signatures::word(deadline)],
+        );
+        self.use_signature(signer, struct_hash, deadline, v, r, s)?;
+        self.start_work_internal(signer, escrow_id)
+    }
+
+    pub fn submit_milestone(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        description: String,
+    ) -> Result<(), Vec<u8>> {
+        self.submit_milestone_internal(self.msg_sender(), escrow_id, milestone_index, description)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_milestone_by_sig(
+        &mut self,
+        signer: Address,
+        escrow_id: U256,
+        milestone_index: U256,
+        description: String,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        let struct_hash = signatures::hash_struct(
+            signatures::SUBMIT_MILESTONE_TYPE,
+            &[
+                signatures::word(escrow_id),
+                signatures::word(milestone_index),
+                crypto::keccak(description.as_bytes()),
+                signatures::word(self.nonces.get(signer)),
+                signatures::word(deadline),
+            ],
+        );
+        self.use_signature(signer, struct_hash, deadline, v, r, s)?;
+        self.submit_milestone_internal(signer, escrow_id, milestone_index, description)
+    }
+
+    pub fn approve_milestone(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        self.approve_milestone_internal(self.msg_sender(), escrow_id, milestone_index)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn approve_milestone_by_sig(
+        &mut self,
+        signer: Address,
+        escrow_id: U256,
+        milestone_index: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        let struct_hash = signatures::hash_struct(
+            signatures::APPROVE_MILESTONE_TYPE,
+            &[
+                signatures::word(escrow_id),
+                signatures::word(milestone_index),
+                signatures::word(self.nonces.get(signer)),
+                signatures::word(deadline),
+            ],
+        );
+        self.use_signature(signer, struct_hash, deadline, v, r, s)?;
+        self.approve_milestone_internal(signer, escrow_id, milestone_index)
+    }
+
+    // Approve several submitted milestones at once; any failure reverts the batch
+    pub fn approve_milestones(&mut self, escrow_id: U256, milestone_indices: Vec<U256>) -> Result<(), Vec<u8>> {
+        let caller = self.msg_sender();
+        for milestone_index in milestone_indices {
+            self.approve_milestone_internal(caller, escrow_id, milestone_index)?;
         }
+        Ok(())
+    }
 
-        if !self.has_applied.get(escrow_id).get(freelancer) {
+    // Submit several milestones at once; any failure reverts the batch
+    pub fn submit_milestones(
+        &mut self,
+        escrow_id: U256,
+        milestone_indices: Vec<U256>,
+        descriptions: Vec<String>,
+    ) -> Result<(), Vec<u8>> {
+        if milestone_indices.len() != descriptions.len() {
+            return Err(Error::MilestoneCountMismatch(String::new()).into());
+        }
+        let caller = self.msg_sender();
+        for (milestone_index, description) in milestone_indices.into_iter().zip(descriptions) {
+            self.submit_milestone_internal(caller, escrow_id, milestone_index, description)?;
+        }
+        Ok(())
+    }
+
+    // The beneficiary splits a milestone's payout across team members.
+    // Shares are basis points summing to 10000, an empty table removes the split.
+    pub fn set_milestone_split(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        recipients: Vec<Address>,
+        shares_bps: Vec<U256>,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.beneficiary.set(freelancer);
-        escrow_mut.is_open_job.set(false);
+        if self.msg_sender() != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
 
-        let mut user_escrows = self.user_escrows.setter(freelancer);
-        user_escrows.push(escrow_id);
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
 
-        Ok(())
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+        drop(escrow);
+
+        self.set_milestone_split_internal(escrow_id, milestone_index, recipients, shares_bps)
     }
 
-    // ===== Refund System =====
-    pub fn refund_escrow(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+    pub fn reject_milestone(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        reason: String,
+    ) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -691,41 +975,46 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
+        if self.msg_sender() != escrow.depositor.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if escrow.status.get() != EscrowStatus::Pending as u8 {
+        if !Self::is_active_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        if escrow.work_started.get() {
-            return Err(Error::WorkNotStarted(String::new()).into());
+        // Time logs are disputed rather than rejected and resubmitted
+        if escrow.escrow_type.get() != EscrowType::Milestone as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
         }
 
-        if U256::from(block::timestamp()) >= escrow.deadline.get() {
-            return Err(Error::DeadlineNotPassed(String::new()).into());
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
         }
 
-        let refund_amount = escrow.total_amount.get() - escrow.paid_amount.get();
-        let token = escrow.token.get();
-        let depositor = escrow.depositor.get();
-        if refund_amount == U256::ZERO {
-            return Err(Error::NothingToRefund(String::new()).into());
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Submitted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.status.set(U8::from(EscrowStatus::Refunded as u8));
-        let escrowed = self.escrowed_amount.get(token);
-        self.escrowed_amount.setter(token).set(escrowed - refund_amount);
-        drop(escrow_mut);
-
-        self.transfer_out(token, depositor, refund_amount)?;
+        let sender = self.msg_sender();
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Rejected as u8));
+        milestone_mut.disputed_at.set(U256::from(block::timestamp()));
+        milestone_mut.disputed_by.set(sender);
+        milestone_mut.dispute_reason.0.set_bytes(reason.as_bytes());
 
         Ok(())
     }
 
-    pub fn emergency_refund_after_deadline(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+    pub fn resubmit_milestone(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        description: String,
+    ) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -733,39 +1022,41 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
+        if self.msg_sender() != escrow.beneficiary.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        let emergency_delay = self.emergency_refund_delay.get();
-        if U256::from(block::timestamp()) <= escrow.deadline.get() + emergency_delay {
-            return Err(Error::EmergencyPeriodNotReached(String::new()).into());
-        }
-
-        let status = escrow.status.get();
-        if status == EscrowStatus::Released as u8 || status == EscrowStatus::Refunded as u8 {
+        if !Self::is_active_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        let refund_amount = escrow.total_amount.get() - escrow.paid_amount.get();
-        let token = escrow.token.get();
-        let depositor = escrow.depositor.get();
-        if refund_amount == U256::ZERO {
-            return Err(Error::NothingToRefund(String::new()).into());
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
         }
 
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.status.set(U8::from(EscrowStatus::Expired as u8));
-        let escrowed = self.escrowed_amount.get(token);
-        self.escrowed_amount.setter(token).set(escrowed - refund_amount);
-        drop(escrow_mut);
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Rejected as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
 
-        self.transfer_out(token, depositor, refund_amount)?;
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Submitted as u8));
+        milestone_mut.submitted_at.set(U256::from(block::timestamp()));
+        if !description.is_empty() {
+            milestone_mut.description.0.set_bytes(description.as_bytes());
+        }
 
         Ok(())
     }
 
-    pub fn extend_deadline(&mut self, escrow_id: U256, extra_seconds: U256) -> Result<(), Vec<u8>> {
+    pub fn dispute_milestone(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        reason: String,
+    ) -> Result<(), Vec<u8>> {
         self.when_not_paused()?;
 
         let escrow = self.escrows.get(escrow_id);
@@ -773,219 +1064,2093 @@ impl SecureFlow {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
 
-        if msg::sender() != escrow.depositor.get() {
+        if self.msg_sender() != escrow.depositor.get() {
             return Err(Error::Unauthorized(String::new()).into());
         }
 
-        if extra_seconds == U256::ZERO || extra_seconds > U256::from(2592000) {
-            return Err(Error::InvalidDuration(String::new()).into());
-        }
-
-        let status = escrow.status.get();
-        if status != EscrowStatus::InProgress as u8 && status != EscrowStatus::Pending as u8 {
+        if !Self::is_active_status(escrow.status.get()) {
             return Err(Error::InvalidStatus(String::new()).into());
         }
 
-        let escrow = self.escrows.get(escrow_id);
-        let new_deadline = escrow.deadline.get() + extra_seconds;
-        let mut escrow_mut = self.escrows.setter(escrow_id);
-        escrow_mut.deadline.set(new_deadline);
-
-        Ok(())
-    }
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
 
-    // ===== Admin Functions =====
+        if escrow.escrow_type.get() == EscrowType::Stream as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        } else if escrow.escrow_type.get() == EscrowType::Retainer as u8 {
+            self.ensure_period_disputable(escrow_id, milestone_index)?;
+        } else {
+            let milestones_map = self.milestones.get(escrow_id);
+            let milestone = milestones_map.get(milestone_index);
+            if milestone.status.get() != MilestoneStatus::Submitted as u8 {
+                return Err(Error::InvalidStatus(String::new()).into());
+            }
 
-    pub fn whitelist_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        if token == Address::ZERO {
-            return Err(Error::InvalidAmount(String::new()).into());
+            let dispute_period = self.dispute_period.get();
+            if U256::from(block::timestamp()) > milestone.submitted_at.get() + dispute_period {
+                return Err(Error::DisputePeriodExpired(String::new()).into());
+            }
         }
-        self.whitelisted_tokens.setter(token).set(true);
-        Ok(())
-    }
+        drop(escrow);
 
-    pub fn blacklist_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.whitelisted_tokens.setter(token).set(false);
-        Ok(())
-    }
+        let sender = self.msg_sender();
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Disputed as u8));
+        milestone_mut.disputed_at.set(U256::from(block::timestamp()));
+        milestone_mut.disputed_by.set(sender);
+        milestone_mut.dispute_reason.0.set_bytes(reason.as_bytes());
+        drop(milestone_mut);
+        drop(milestones_map_mut);
 
-    pub fn authorize_arbiter(&mut self, arbiter: Address) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        if arbiter == Address::ZERO {
-            return Err(Error::InvalidAmount(String::new()).into());
+        let response_period = self.dispute_response_period.get();
+        let response_window = if response_period == U256::ZERO { U256::from(259200) } else { response_period };
+        let mut disputes_map = self.disputes.setter(escrow_id);
+        let mut dispute = disputes_map.setter(milestone_index);
+        dispute.response_deadline.set(U256::from(block::timestamp()) + response_window);
+        dispute.counterparty_responded.set(false);
+        drop(dispute);
+        drop(disputes_map);
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        let open_disputes = escrow_mut.open_disputes.get();
+        escrow_mut.open_disputes.set(open_disputes + U256::from(1));
+        drop(escrow_mut);
+        self.sync_escrow_status(escrow_id);
+        if self.needs_arbiter_panel(escrow_id) {
+            let seed_block = self.schedule_draw()?;
+            self.disputes.setter(escrow_id).setter(milestone_index).panel_seed_block.set(seed_block);
         }
-        self.authorized_arbiters.setter(arbiter).set(true);
-        Ok(())
-    }
 
-    pub fn revoke_arbiter(&mut self, arbiter: Address) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.authorized_arbiters.setter(arbiter).set(false);
         Ok(())
     }
 
-    pub fn pause_job_creation(&mut self) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.job_creation_paused.set(true);
-        Ok(())
-    }
+    // Anyone can run a panel draw scheduled by dispute_milestone or appeal_ruling
+    // once its seed block is mined. Returns false when the seed block aged out
+    // and the draw was rescheduled against a new one.
+    pub fn draw_dispute_panel(&mut self, escrow_id: U256, milestone_index: U256) -> Result<bool, Vec<u8>> {
+        self.when_not_paused()?;
 
-    pub fn unpause_job_creation(&mut self) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.job_creation_paused.set(false);
-        Ok(())
-    }
+        let milestones_map = self.milestones.get(escrow_id);
+        if milestones_map.get(milestone_index).status.get() != MilestoneStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        drop(milestones_map);
 
-    pub fn pause(&mut self) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.paused.set(true);
-        Ok(())
-    }
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let seed_block = dispute.panel_seed_block.get();
+        let appealed = dispute.appealed.get();
+        drop(dispute);
+        drop(disputes_map);
+        if seed_block == U256::ZERO {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
 
-    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
-        self.paused.set(false);
-        Ok(())
-    }
+        let Some(seed) = self.panel_seed(seed_block, escrow_id, milestone_index)? else {
+            let next_block = self.schedule_draw()?;
+            self.disputes.setter(escrow_id).setter(milestone_index).panel_seed_block.set(next_block);
+            return Ok(false);
+        };
+        self.disputes.setter(escrow_id).setter(milestone_index).panel_seed_block.set(U256::ZERO);
 
+        if appealed {
+            self.assign_appeal_panel(escrow_id, milestone_index, seed)?;
+        } else {
+            self.assign_arbiter_panel(escrow_id, seed)?;
+        }
 
-    // ===== View Functions =====
-    pub fn next_escrow_id(&self) -> Result<U256, Vec<u8>> {
-        Ok(self.next_escrow_id.get())
+        Ok(true)
     }
 
-    pub fn owner(&self) -> Result<Address, Vec<u8>> {
-        Ok(self.owner.get())
-    }
-
-    pub fn paused(&self) -> Result<bool, Vec<u8>> {
-        Ok(self.paused.get())
-    }
+    // Either party may attach evidence while the milestone is disputed.
+    // The first entry from the counterparty counts as their response.
+    pub fn submit_evidence(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        content_ref: String,
+        note: String,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
 
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
 
-    pub fn job_creation_paused(&self) -> Result<bool, Vec<u8>> {
-        Ok(self.job_creation_paused.get())
-    }
+        let sender = self.msg_sender();
+        if sender != escrow.depositor.get() && sender != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
 
-    pub fn authorized_arbiters(&self, arbiter: Address) -> Result<bool, Vec<u8>> {
-        Ok(self.authorized_arbiters.get(arbiter))
-    }
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        let disputed_by = milestone.disputed_by.get();
 
-    pub fn whitelisted_tokens(&self, token: Address) -> Result<bool, Vec<u8>> {
-        Ok(self.whitelisted_tokens.get(token))
-    }
+        let max_note = self.max_evidence_note_length.get();
+        let max_note_len = if max_note == U256::ZERO { 280usize } else { max_note.as_limbs()[0] as usize };
+        if content_ref.is_empty() || note.len() > max_note_len {
+            return Err(Error::InvalidEvidence(String::new()).into());
+        }
 
-    pub fn reputation(&self, user: Address) -> Result<U256, Vec<u8>> {
-        Ok(self.reputation.get(user))
-    }
+        let max_evidence = self.max_evidence_per_dispute.get();
+        let max_evidence_count = if max_evidence == U256::ZERO { 20usize } else { max_evidence.as_limbs()[0] as usize };
 
-    pub fn completed_escrows(&self, user: Address) -> Result<U256, Vec<u8>> {
-        Ok(self.completed_escrows.get(user))
-    }
+        let mut disputes_map = self.disputes.setter(escrow_id);
+        let mut dispute = disputes_map.setter(milestone_index);
+        if dispute.evidence.len() >= max_evidence_count {
+            return Err(Error::TooMuchEvidence(String::new()).into());
+        }
 
-    pub fn escrowed_amount(&self, token: Address) -> Result<U256, Vec<u8>> {
-        Ok(self.escrowed_amount.get(token))
-    }
+        let mut entry = dispute.evidence.grow();
+        entry.submitter.set(sender);
+        entry.content_ref.0.set_bytes(content_ref.as_bytes());
+        entry.note.0.set_bytes(note.as_bytes());
+        entry.submitted_at.set(U256::from(block::timestamp()));
+        drop(entry);
 
+        if sender != disputed_by {
+            dispute.counterparty_responded.set(true);
+        }
 
-    pub fn has_applied(&self, escrow_id: U256, user: Address) -> Result<bool, Vec<u8>> {
-        Ok(self.has_applied.get(escrow_id).get(user))
+        Ok(())
     }
 
-    pub fn is_arbiter_for_escrow(&self, escrow_id: U256, arbiter: Address) -> Result<bool, Vec<u8>> {
-        Ok(self.is_arbiter_for_escrow_internal(escrow_id, arbiter))
-    }
+    // Parties settle by proposing the same split; neither can decide alone.
+    // Arbiters vote instead, and the panel's decision takes effect once
+    // `required_confirmations` of them agree (a majority of an appeal panel).
+    pub fn resolve_dispute(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        beneficiary_amount: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
 
-    pub fn get_escrow_summary(&self, escrow_id: U256) -> Result<(Address, Address, Vec<Address>, U256, U256, U256, U256, Address, U256, bool, U256, U256, bool, String, String), Vec<u8>> {
         let escrow = self.escrows.get(escrow_id);
         if escrow.depositor.get() == Address::ZERO {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
-        let remaining = escrow.total_amount.get() - escrow.paid_amount.get();
-        let arbiters_vec = &escrow.arbiters;
-        let mut arbiters_list = Vec::new();
-        let mut i = 0;
-        loop {
-            if let Some(addr) = arbiters_vec.get(i) {
-                arbiters_list.push(addr);
-                i += 1;
+
+        let sender = self.msg_sender();
+        let is_party = sender == escrow.depositor.get() || sender == escrow.beneficiary.get();
+
+        if escrow.status.get() != EscrowStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if beneficiary_amount > milestone.amount.get() {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let appealed = dispute.appealed.get();
+        let required = if appealed {
+            dispute.appeal_panel.len() / 2 + 1
+        } else {
+            escrow.required_confirmations.get().to::<usize>()
+        };
+
+        if !is_party {
+            let is_panel_member = if appealed {
+                dispute.appeal_panel_member.get(sender)
             } else {
-                break;
+                self.is_arbiter_for_escrow_internal(escrow_id, sender)
+            };
+            if !is_panel_member {
+                return Err(Error::Unauthorized(String::new()).into());
+            }
+
+            // Seats held by revoked or under-staked arbiters stay on the escrow until replaced, but can't rule
+            if !self.is_staked_arbiter(sender) {
+                return Err(Error::ArbiterNotAuthorized(String::new()).into());
+            }
+
+            if dispute.ruling_pending.get() && !appealed {
+                return Err(Error::RulingPending(String::new()).into());
+            }
+
+            // Once the appeal panel runs out of time the original ruling stands
+            if appealed
+                && U256::from(block::timestamp()) > dispute.appealed_at.get() + self.appeal_voting_period.get()
+            {
+                return Err(Error::AppealExpired(String::new()).into());
+            }
+
+            // Arbiters wait for the counterparty's response or the response deadline
+            if !dispute.counterparty_responded.get()
+                && U256::from(block::timestamp()) <= dispute.response_deadline.get()
+            {
+                return Err(Error::ResponsePeriodActive(String::new()).into());
             }
         }
-        Ok((
-            escrow.depositor.get(),
-            escrow.beneficiary.get(),
-            arbiters_list,
-            U256::from(escrow.status.get()), // Return as U256 (Stylus doesn't support U8 in return tuples)
-            escrow.total_amount.get(),
-            escrow.paid_amount.get(),
-            remaining,
-            escrow.token.get(),
-            escrow.deadline.get(),
-            escrow.work_started.get(),
-            escrow.created_at.get(),
-            escrow.milestone_count.get(),
-            escrow.is_open_job.get(),
-            escrow.project_title.get_string(),
-            escrow.project_description.get_string(),
-        ))
-    }
+        drop(escrow);
 
-    pub fn get_milestones(&self, _escrow_id: U256) -> Result<Vec<u8>, Vec<u8>> {
-        // Return empty vec - milestone serialization would need custom encoding
-        Ok(Vec::new())
+        if is_party {
+            if self.record_settlement_offer(escrow_id, milestone_index, sender, beneficiary_amount) {
+                self.release_appeal_bond(escrow_id, milestone_index)?;
+                return self.execute_ruling(escrow_id, milestone_index, beneficiary_amount, &[]);
+            }
+            return Ok(());
+        }
+
+        match self.record_arbiter_vote(escrow_id, milestone_index, sender, beneficiary_amount, required)? {
+            Some(panel) => self.conclude_panel_vote(escrow_id, milestone_index, beneficiary_amount, panel),
+            None => Ok(()),
+        }
     }
 
-    pub fn get_user_escrows(&self, user: Address) -> Result<Vec<U256>, Vec<u8>> {
-        let escrows_vec = &self.user_escrows.get(user);
-        let mut escrows_list = Vec::new();
-        let mut i = 0;
-        loop {
-            if let Some(escrow_id) = escrows_vec.get(i) {
-                escrows_list.push(escrow_id);
-                i += 1;
-            } else {
-                break;
+    // A party the ruling did not fully favour can escalate it to a larger panel
+    // by posting a bond while the appeal window is open. The panel is drawn
+    // afterwards with draw_dispute_panel.
+    #[payable]
+    pub fn appeal_ruling(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        let sender = self.msg_sender();
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        if sender != depositor && sender != beneficiary {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+        // The bond is posted in the disputed milestone's token
+        let token = self.milestone_token(escrow_id, milestone_index);
+
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        let milestone_amount = milestone.amount.get();
+
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        if !dispute.ruling_pending.get() || dispute.appealed.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if U256::from(block::timestamp()) > dispute.ruled_at.get() + self.appeal_window.get() {
+            return Err(Error::AppealWindowClosed(String::new()).into());
+        }
+
+        let ruled_amount = dispute.ruled_amount.get();
+        let won_everything = if sender == depositor {
+            ruled_amount == U256::ZERO
+        } else {
+            ruled_amount == milestone_amount
+        };
+        if won_everything {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        drop(escrow);
+
+        let bond = milestone_amount * self.appeal_bond_bps.get() / U256::from(10_000);
+        if token == Address::ZERO {
+            if msg::value() != bond {
+                return Err(Error::ValueMismatch(String::new()).into());
             }
+        } else {
+            if msg::value() != U256::ZERO {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            self.transfer_in(token, sender, bond)?;
         }
-        Ok(escrows_list)
+
+        let seed_block = self.schedule_draw()?;
+
+        let mut disputes_map_mut = self.disputes.setter(escrow_id);
+        let mut dispute_mut = disputes_map_mut.setter(milestone_index);
+        dispute_mut.appealed.set(true);
+        dispute_mut.appellant.set(sender);
+        dispute_mut.appeal_bond.set(bond);
+        dispute_mut.appealed_at.set(U256::from(block::timestamp()));
+        dispute_mut.panel_seed_block.set(seed_block);
+
+        Ok(())
     }
 
-    pub fn get_applications_page(
-        &self,
-        _escrow_id: U256,
-        _offset: U256,
-        _limit: U256,
-    ) -> Result<Vec<u8>, Vec<u8>> {
-        // Return empty vec - Application serialization would need custom encoding
-        Ok(Vec::new())
+    // Anyone can execute a panel decision once its appeal window passes unchallenged,
+    // or once an appeal panel fails to rule within the voting period. In the latter
+    // case the original ruling stands and the appellant gets the bond back.
+    pub fn finalize_ruling(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let milestones_map = self.milestones.get(escrow_id);
+        if milestones_map.get(milestone_index).status.get() != MilestoneStatus::Disputed as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        drop(milestones_map);
+
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        if !dispute.ruling_pending.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let now = U256::from(block::timestamp());
+        let appealed = dispute.appealed.get();
+        if appealed {
+            if now <= dispute.appealed_at.get() + self.appeal_voting_period.get() {
+                return Err(Error::AppealWindowOpen(String::new()).into());
+            }
+        } else if now <= dispute.ruled_at.get() + self.appeal_window.get() {
+            return Err(Error::AppealWindowOpen(String::new()).into());
+        }
+
+        let ruled_amount = dispute.ruled_amount.get();
+        let mut panel = Vec::new();
+        let mut i = 0;
+        while let Some(arbiter) = dispute.ruling_panel.get(i) {
+            panel.push(arbiter);
+            i += 1;
+        }
+        drop(dispute);
+        drop(disputes_map);
+
+        if appealed {
+            self.release_appeal_bond(escrow_id, milestone_index)?;
+        }
+        self.execute_ruling(escrow_id, milestone_index, ruled_amount, &panel)
     }
 
-    pub fn get_application_count(&self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+    // ===== Marketplace =====
+    pub fn apply_to_job(
+        &mut self,
+        escrow_id: U256,
+        cover_letter: String,
+        proposed_timeline: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+        let applicant = self.msg_sender();
+
         let escrow = self.escrows.get(escrow_id);
         if escrow.depositor.get() == Address::ZERO {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
-        Ok(U256::from(self.escrow_applications.get(escrow_id).len()))
+
+        if !escrow.is_open_job.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if escrow.status.get() != EscrowStatus::Pending as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if self.has_applied.get(escrow_id).get(applicant) {
+            return Err(Error::AlreadySubmitted(String::new()).into());
+        }
+
+        let max_apps = self.max_applications.get();
+        let mut applications = self.escrow_applications.setter(escrow_id);
+        if U256::from(applications.len()) >= max_apps {
+            return Err(Error::TooManyMilestones(String::new()).into());
+        }
+
+        if applicant == escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if cover_letter.is_empty() {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+          // Create application using grow() to get a mutable accessor
+          let mut app = applications.grow();
+          app.freelancer.set(applicant);
+          app.cover_letter.0.set_bytes(cover_letter.as_bytes());
+          app.proposed_timeline.set(proposed_timeline);
+          app.applied_at.set(U256::from(block::timestamp()));
+          app.exists.set(true);
+        self.has_applied.setter(escrow_id).setter(applicant).set(true);
+
+        Ok(())
     }
 
-    pub fn has_user_applied(&self, escrow_id: U256, user: Address) -> Result<bool, Vec<u8>> {
+    pub fn accept_freelancer(&mut self, escrow_id: U256, freelancer: Address) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
         let escrow = self.escrows.get(escrow_id);
         if escrow.depositor.get() == Address::ZERO {
             return Err(Error::InvalidEscrow(String::new()).into());
         }
-        Ok(self.has_applied.get(escrow_id).get(user))
-    }
 
-    pub fn get_reputation(&self, user: Address) -> Result<U256, Vec<u8>> {
-        Ok(self.reputation.get(user))
-    }
+        if self.msg_sender() != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
 
-    pub fn get_completed_escrows(&self, user: Address) -> Result<U256, Vec<u8>> {
-        Ok(self.completed_escrows.get(user))
+        if !escrow.is_open_job.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if escrow.status.get() != EscrowStatus::Pending as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if !self.has_applied.get(escrow_id).get(freelancer) {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if self.is_arbiter_for_escrow_internal(escrow_id, freelancer) {
+            return Err(Error::ConflictOfInterest(String::new()).into());
+        }
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.beneficiary.set(freelancer);
+        escrow_mut.is_open_job.set(false);
+
+        let mut user_escrows = self.user_escrows.setter(freelancer);
+        user_escrows.push(escrow_id);
+        drop(user_escrows);
+        self.track_open_escrow(freelancer);
+
+        Ok(())
     }
 
+    // ===== Refund System =====
+    // Before work starts the depositor may take everything back at any time.
+    // Once work has started, only milestones that were never submitted can be
+    // refunded, and only after the deadline has passed.
+    pub fn refund_escrow(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        // Other escrow types have their own cancellation once work starts
+        if escrow.work_started.get() && escrow.escrow_type.get() != EscrowType::Milestone as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if escrow.work_started.get() && U256::from(block::timestamp()) <= escrow.deadline.get() {
+            return Err(Error::DeadlineNotPassed(String::new()).into());
+        }
+        let is_hourly = escrow.escrow_type.get() == EscrowType::Hourly as u8;
+        drop(escrow);
+
+        // An hourly budget is not held in any milestone
+        if is_hourly {
+            if self.close_hourly_internal(escrow_id)? == U256::ZERO {
+                return Err(Error::NothingToRefund(String::new()).into());
+            }
+            return Ok(());
+        }
+
+        self.refund_open_milestones(escrow_id, false, EscrowStatus::Refunded)?;
+
+        Ok(())
+    }
+
+    // Last resort once the emergency delay has elapsed: also reclaims rejected
+    // milestones the beneficiary never resubmitted. Submitted and disputed
+    // milestones remain frozen until approved or resolved.
+    pub fn emergency_refund_after_deadline(&mut self, escrow_id: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        let emergency_delay = self.emergency_refund_delay.get();
+        if U256::from(block::timestamp()) <= escrow.deadline.get() + emergency_delay {
+            return Err(Error::EmergencyPeriodNotReached(String::new()).into());
+        }
+
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        // Vested stream funds belong to the beneficiary, streams use cancel_stream
+        if escrow.escrow_type.get() == EscrowType::Stream as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+        let is_retainer = escrow.escrow_type.get() == EscrowType::Retainer as u8;
+        drop(escrow);
+
+        // Elapsed retainer periods belong to the beneficiary even if unclaimed
+        if is_retainer {
+            self.refund_future_periods(escrow_id, EscrowStatus::Expired)?;
+        } else {
+            self.refund_open_milestones(escrow_id, true, EscrowStatus::Expired)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn extend_deadline(&mut self, escrow_id: U256, extra_seconds: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if extra_seconds == U256::ZERO || extra_seconds > U256::from(2592000) {
+            return Err(Error::InvalidDuration(String::new()).into());
+        }
+
+        let status = escrow.status.get();
+        if !Self::is_active_status(status) && status != EscrowStatus::Pending as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let new_deadline = escrow.deadline.get() + extra_seconds;
+        // A child escrow cannot outlive the parent milestone that funds it
+        if escrow.is_child.get() && new_deadline > self.escrows.get(escrow.parent_id.get()).deadline.get() {
+            return Err(Error::InvalidDuration(String::new()).into());
+        }
+        drop(escrow);
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.deadline.set(new_deadline);
+
+        Ok(())
+    }
+
+    // Depositor sets the fee before work starts; the beneficiary accepts it by starting work
+    pub fn set_arbitration_fee(&mut self, escrow_id: U256, fixed_fee: U256, fee_bps: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if self.msg_sender() != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if escrow.status.get() != EscrowStatus::Pending as u8 || escrow.work_started.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if fee_bps > U256::from(10_000) {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        // A flat fee has no single unit when milestones are paid in different tokens
+        if escrow.multi_token.get() && fixed_fee > U256::ZERO {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.arbitration_fee.set(fixed_fee);
+        escrow_mut.arbitration_fee_bps.set(fee_bps);
+
+        Ok(())
+    }
+
+    pub fn claim_arbitration_fees(&mut self, token: Address) -> Result<U256, Vec<u8>> {
+        let arbiter = self.msg_sender();
+        let amount = self.arbiter_fee_balances.get(arbiter).get(token);
+        if amount == U256::ZERO {
+            return Err(Error::NoFeesToClaim(String::new()).into());
+        }
+
+        self.arbiter_fee_balances.setter(arbiter).setter(token).set(U256::ZERO);
+        let held = self.arbitration_fees_held.get(token);
+        self.arbitration_fees_held.setter(token).set(held - amount);
+        self.transfer_out(token, arbiter, amount)?;
+
+        Ok(amount)
+    }
+
+    // ===== Arbiter Replacement =====
+    // One party proposes swapping an arbiter on a live escrow, the other confirms.
+    // Open jobs without a beneficiary yet only need the depositor.
+    pub fn propose_arbiter_replacement(
+        &mut self,
+        escrow_id: U256,
+        old_arbiter: Address,
+        new_arbiter: Address,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        let sender = self.msg_sender();
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        if sender != depositor && sender != beneficiary {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if !self.is_arbiter_for_escrow_internal(escrow_id, old_arbiter) {
+            return Err(Error::ArbiterNotAuthorized(String::new()).into());
+        }
+
+        if self.is_arbiter_for_escrow_internal(escrow_id, new_arbiter) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if new_arbiter == depositor || new_arbiter == beneficiary {
+            return Err(Error::ConflictOfInterest(String::new()).into());
+        }
+
+        if !self.is_eligible_arbiter(new_arbiter, depositor, beneficiary) {
+            return Err(Error::ArbiterNotAuthorized(String::new()).into());
+        }
+        drop(escrow);
+
+        if beneficiary == Address::ZERO {
+            self.replace_escrow_arbiter(escrow_id, old_arbiter, new_arbiter);
+            return Ok(());
+        }
+
+        let mut proposal = self.arbiter_replacements.setter(escrow_id);
+        proposal.old_arbiter.set(old_arbiter);
+        proposal.new_arbiter.set(new_arbiter);
+        proposal.proposed_by.set(sender);
+
+        Ok(())
+    }
+
+    pub fn confirm_arbiter_replacement(
+        &mut self,
+        escrow_id: U256,
+        old_arbiter: Address,
+        new_arbiter: Address,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        let sender = self.msg_sender();
+        if sender != escrow.depositor.get() && sender != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let proposal = self.arbiter_replacements.get(escrow_id);
+        let proposed_by = proposal.proposed_by.get();
+        if proposed_by == Address::ZERO
+            || proposed_by == sender
+            || proposal.old_arbiter.get() != old_arbiter
+            || proposal.new_arbiter.get() != new_arbiter
+        {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        // Membership may have changed since the proposal was made
+        if !self.is_arbiter_for_escrow_internal(escrow_id, old_arbiter)
+            || self.is_arbiter_for_escrow_internal(escrow_id, new_arbiter)
+        {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        drop(escrow);
+
+        let mut proposal_mut = self.arbiter_replacements.setter(escrow_id);
+        proposal_mut.old_arbiter.set(Address::ZERO);
+        proposal_mut.new_arbiter.set(Address::ZERO);
+        proposal_mut.proposed_by.set(Address::ZERO);
+        drop(proposal_mut);
+
+        self.replace_escrow_arbiter(escrow_id, old_arbiter, new_arbiter);
+
+        Ok(())
+    }
+
+    // Either party can swap a revoked or under-staked arbiter for one drawn from the pool.
+    // This schedules the draw and returns its seed block, draw_replacement_arbiter runs it.
+    // To pick a specific replacement, use the propose/confirm flow above.
+    pub fn replace_revoked_arbiter(&mut self, escrow_id: U256, revoked_arbiter: Address) -> Result<U256, Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        let sender = self.msg_sender();
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        if sender != depositor && sender != beneficiary {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if Self::is_closed_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if !self.is_arbiter_for_escrow_internal(escrow_id, revoked_arbiter) {
+            return Err(Error::ArbiterNotAuthorized(String::new()).into());
+        }
+
+        if self.is_staked_arbiter(revoked_arbiter) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        drop(escrow);
+
+        let seed_block = self.schedule_draw()?;
+        self.replacement_seed_blocks.setter(escrow_id).setter(revoked_arbiter).set(seed_block);
+
+        Ok(seed_block)
+    }
+
+    // Anyone can run a replacement draw once its seed block is mined. Returns
+    // false when the seed block aged out and the draw was rescheduled.
+    pub fn draw_replacement_arbiter(&mut self, escrow_id: U256, revoked_arbiter: Address) -> Result<bool, Vec<u8>> {
+        self.when_not_paused()?;
+
+        let seed_block = self.replacement_seed_blocks.get(escrow_id).get(revoked_arbiter);
+        if seed_block == U256::ZERO {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let escrow = self.escrows.get(escrow_id);
+        let depositor = escrow.depositor.get();
+        let beneficiary = escrow.beneficiary.get();
+        let closed = Self::is_closed_status(escrow.status.get());
+        drop(escrow);
+        // The arbiter may have been replaced or reinstated since the draw was scheduled
+        if closed
+            || !self.is_arbiter_for_escrow_internal(escrow_id, revoked_arbiter)
+            || self.is_staked_arbiter(revoked_arbiter)
+        {
+            self.replacement_seed_blocks.setter(escrow_id).setter(revoked_arbiter).set(U256::ZERO);
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        let key = U256::from_be_slice(revoked_arbiter.as_slice());
+        let Some(seed) = self.panel_seed(seed_block, escrow_id, key)? else {
+            let next_block = self.schedule_draw()?;
+            self.replacement_seed_blocks.setter(escrow_id).setter(revoked_arbiter).set(next_block);
+            return Ok(false);
+        };
+        self.replacement_seed_blocks.setter(escrow_id).setter(revoked_arbiter).set(U256::ZERO);
+
+        let current = self.escrow_arbiters(escrow_id);
+        let replacement = self.draw_arbiters(seed, 1, depositor, beneficiary, &current)?[0];
+        self.replace_escrow_arbiter(escrow_id, revoked_arbiter, replacement);
+
+        Ok(true)
+    }
+
+    // ===== Arbiter Registry =====
+    // Staking at least `min_arbiter_stake` registers the caller as an authorized arbiter.
+    // Revoked arbiters cannot stake their way back, and self-registration is off
+    // while the minimum stake is zero.
+    #[payable]
+    pub fn stake_as_arbiter(&mut self, amount: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        if amount == U256::ZERO {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        if self.min_arbiter_stake.get() == U256::ZERO {
+            return Err(Error::StakingDisabled(String::new()).into());
+        }
+
+        let arbiter = self.msg_sender();
+        if self.revoked_arbiters.get(arbiter) {
+            return Err(Error::ArbiterRevoked(String::new()).into());
+        }
+
+        let token = self.arbiter_stake_token.get();
+        self.ensure_accepts_deposits(token)?;
+        if token == Address::ZERO {
+            if msg::value() != amount {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+        } else {
+            if msg::value() != U256::ZERO {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            self.transfer_in(token, arbiter, amount)?;
+        }
+
+        let mut info = self.arbiter_info.setter(arbiter);
+        let stake = info.stake.get() + amount;
+        info.stake.set(stake);
+        drop(info);
+        let total = self.total_arbiter_stake.get();
+        self.total_arbiter_stake.set(total + amount);
+
+        if stake < self.min_arbiter_stake.get() {
+            return Err(Error::InsufficientStake(String::new()).into());
+        }
+        self.authorized_arbiters.setter(arbiter).set(true);
+        self.add_to_arbiter_pool(arbiter);
+
+        Ok(())
+    }
+
+    // Moves stake into the cooldown queue; it stays slashable until withdrawn
+    pub fn request_unstake(&mut self, amount: U256) -> Result<(), Vec<u8>> {
+        let arbiter = self.msg_sender();
+        let cooldown = self.unstake_cooldown.get();
+        let mut info = self.arbiter_info.setter(arbiter);
+        let stake = info.stake.get();
+        if amount == U256::ZERO || amount > stake {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        info.stake.set(stake - amount);
+        let pending = info.pending_unstake.get();
+        info.pending_unstake.set(pending + amount);
+        info.unstake_available_at.set(U256::from(block::timestamp()) + cooldown);
+
+        Ok(())
+    }
+
+    pub fn withdraw_stake(&mut self) -> Result<U256, Vec<u8>> {
+        let arbiter = self.msg_sender();
+        let mut info = self.arbiter_info.setter(arbiter);
+        let amount = info.pending_unstake.get();
+        if amount == U256::ZERO {
+            return Err(Error::NothingToRefund(String::new()).into());
+        }
+        if U256::from(block::timestamp()) < info.unstake_available_at.get() {
+            return Err(Error::CooldownActive(String::new()).into());
+        }
+        info.pending_unstake.set(U256::ZERO);
+        drop(info);
+
+        let total = self.total_arbiter_stake.get();
+        self.total_arbiter_stake.set(total - amount);
+        let token = self.arbiter_stake_token.get();
+        self.transfer_out(token, arbiter, amount)?;
+
+        Ok(amount)
+    }
+
+    // Slashes active stake first, then stake waiting out the cooldown
+    pub fn slash_arbiter(&mut self, arbiter: Address, amount: U256, recipient: Address) -> Result<U256, Vec<u8>> {
+        self.only_owner_or_governance()?;
+        if recipient == Address::ZERO {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+
+        let mut info = self.arbiter_info.setter(arbiter);
+        let stake = info.stake.get();
+        let pending = info.pending_unstake.get();
+        let from_stake = amount.min(stake);
+        let from_pending = (amount - from_stake).min(pending);
+        let slashed = from_stake + from_pending;
+        if slashed == U256::ZERO {
+            return Err(Error::NothingToRefund(String::new()).into());
+        }
+        info.stake.set(stake - from_stake);
+        info.pending_unstake.set(pending - from_pending);
+        let total_slashed = info.total_slashed.get();
+        info.total_slashed.set(total_slashed + slashed);
+        drop(info);
+
+        let total = self.total_arbiter_stake.get();
+        self.total_arbiter_stake.set(total - slashed);
+        let token = self.arbiter_stake_token.get();
+        self.transfer_out(token, recipient, slashed)?;
+
+        Ok(slashed)
+    }
+
+    // ===== Admin Functions =====
+
+    pub fn set_arbiter_staking(&mut self, token: Address, min_stake: U256, cooldown: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.ensure_accepts_deposits(token)?;
+        // Existing stakes must be paid back in the token they were made in
+        if token != self.arbiter_stake_token.get() && self.total_arbiter_stake.get() != U256::ZERO {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        self.arbiter_stake_token.set(token);
+        self.min_arbiter_stake.set(min_stake);
+        self.unstake_cooldown.set(cooldown);
+        Ok(())
+    }
+
+    // A zero window disables appeals and panel decisions pay out immediately
+    pub fn set_appeal_config(&mut self, window: U256, bond_bps: U256, voting_period: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if bond_bps > U256::from(10_000) {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        if voting_period == U256::ZERO {
+            return Err(Error::InvalidDuration(String::new()).into());
+        }
+        self.appeal_window.set(window);
+        self.appeal_bond_bps.set(bond_bps);
+        self.appeal_voting_period.set(voting_period);
+        Ok(())
+    }
+
+    pub fn set_governance(&mut self, governance: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.governance.set(governance);
+        Ok(())
+    }
+
+    // Zero disables forwarding
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.trusted_forwarder.set(forwarder);
+        Ok(())
+    }
+
+    // Zero falls back to the canonical Permit2 deployment
+    pub fn set_permit2(&mut self, permit2: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.permit2.set(permit2);
+        Ok(())
+    }
+
+    pub fn set_default_arbitration_fee(&mut self, fixed_fee: U256, fee_bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if fee_bps > U256::from(10_000) {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        self.default_arbitration_fee.set(fixed_fee);
+        self.default_arbitration_fee_bps.set(fee_bps);
+        Ok(())
+    }
+
+    pub fn whitelist_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if token == Address::ZERO {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        self.set_token_status_internal(token, TokenStatus::Active);
+        self.refresh_token_metadata_internal(token);
+        Ok(())
+    }
+
+    // 0 Unlisted, 1 Active, 2 DepositsPaused, 3 Deprecated. Payouts on existing
+    // escrows never depend on the token's status.
+    pub fn set_token_status(&mut self, token: Address, status: u8) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let status = match status {
+            0 => TokenStatus::Unlisted,
+            1 => TokenStatus::Active,
+            2 => TokenStatus::DepositsPaused,
+            3 => TokenStatus::Deprecated,
+            _ => return Err(Error::InvalidStatus(String::new()).into()),
+        };
+        self.set_token_status_internal(token, status);
+        Ok(())
+    }
+
+    // Zero for any limit disables it. Address::ZERO configures native ETH.
+    pub fn configure_token(
+        &mut self,
+        token: Address,
+        min_escrow: U256,
+        max_escrow: U256,
+        max_total_escrowed: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if max_escrow != U256::ZERO && min_escrow > max_escrow {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        let mut config = self.token_configs.setter(token);
+        config.min_escrow.set(min_escrow);
+        config.max_escrow.set(max_escrow);
+        config.max_total_escrowed.set(max_total_escrowed);
+        drop(config);
+        self.refresh_token_metadata_internal(token);
+        Ok(())
+    }
+
+    pub fn refresh_token_metadata(&mut self, token: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.refresh_token_metadata_internal(token);
+        Ok(())
+    }
+
+    // Stops new escrows in the token; live escrows can still release and refund
+    pub fn blacklist_token(&mut self, token: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.set_token_status_internal(token, TokenStatus::Deprecated);
+        Ok(())
+    }
+
+    pub fn authorize_arbiter(&mut self, arbiter: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if arbiter == Address::ZERO {
+            return Err(Error::InvalidAmount(String::new()).into());
+        }
+        self.revoked_arbiters.setter(arbiter).set(false);
+        self.authorized_arbiters.setter(arbiter).set(true);
+        self.add_to_arbiter_pool(arbiter);
+        Ok(())
+    }
+
+    pub fn revoke_arbiter(&mut self, arbiter: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.authorized_arbiters.setter(arbiter).set(false);
+        self.revoked_arbiters.setter(arbiter).set(true);
+        self.remove_from_arbiter_pool(arbiter);
+        Ok(())
+    }
+
+    pub fn pause_job_creation(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.job_creation_paused.set(true);
+        Ok(())
+    }
+
+    pub fn unpause_job_creation(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.job_creation_paused.set(false);
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.paused.set(true);
+        Ok(())
+    }
+
+    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.paused.set(false);
+        Ok(())
+    }
+
+    // ===== View Functions =====
+    pub fn next_escrow_id(&self) -> Result<U256, Vec<u8>> {
+        Ok(self.next_escrow_id.get())
+    }
+
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.owner.get())
+    }
+
+    pub fn paused(&self) -> Result<bool, Vec<u8>> {
+        Ok(self.paused.get())
+    }
+
+    pub fn job_creation_paused(&self) -> Result<bool, Vec<u8>> {
+        Ok(self.job_creation_paused.get())
+    }
+
+    pub fn authorized_arbiters(&self, arbiter: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.authorized_arbiters.get(arbiter))
+    }
+
+    pub fn whitelisted_tokens(&self, token: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.token_status.get(token) == TokenStatus::Active as u8)
+    }
+
+    pub fn token_status(&self, token: Address) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.token_status.get(token)))
+    }
+
+    pub fn reputation(&self, user: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.reputation.get(user))
+    }
+
+    pub fn completed_escrows(&self, user: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.completed_escrows.get(user))
+    }
+
+    pub fn escrowed_amount(&self, token: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.escrowed_amount.get(token))
+    }
+
+    pub fn has_applied(&self, escrow_id: U256, user: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.has_applied.get(escrow_id).get(user))
+    }
+
+    pub fn get_open_disputes(&self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        Ok(escrow.open_disputes.get())
+    }
+
+    pub fn get_arbitration_fee(&self, escrow_id: U256) -> Result<(U256, U256), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        Ok((escrow.arbitration_fee.get(), escrow.arbitration_fee_bps.get()))
+    }
+
+    // Returns (authorized, stake, pending unstake, unstake available at, cases handled, cases overturned, total slashed)
+    #[allow(clippy::type_complexity)]
+    pub fn get_arbiter_info(
+        &self,
+        arbiter: Address,
+    ) -> Result<(bool, U256, U256, U256, U256, U256, U256), Vec<u8>> {
+        let info = self.arbiter_info.get(arbiter);
+        Ok((
+            self.authorized_arbiters.get(arbiter),
+            info.stake.get(),
+            info.pending_unstake.get(),
+            info.unstake_available_at.get(),
+            info.cases_handled.get(),
+            info.cases_overturned.get(),
+            info.total_slashed.get(),
+        ))
+    }
+
+    pub fn get_arbiter_pool_size(&self) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.arbiter_pool.len()))
+    }
+
+    pub fn get_authorized_arbiters(&self, offset: U256, limit: U256) -> Result<Vec<Address>, Vec<u8>> {
+        Ok(enumerable::page(&self.arbiter_pool, offset, limit))
+    }
+
+    // Returns (accepting deposits, min escrow, max escrow, max total escrowed, currently escrowed, decimals, symbol)
+    #[allow(clippy::type_complexity)]
+    pub fn get_token_config(
+        &self,
+        token: Address,
+    ) -> Result<(bool, U256, U256, U256, U256, U256, String), Vec<u8>> {
+        let config = self.token_configs.get(token);
+        Ok((
+            self.ensure_accepts_deposits(token).is_ok(),
+            config.min_escrow.get(),
+            config.max_escrow.get(),
+            config.max_total_escrowed.get(),
+            self.escrowed_amount.get(token),
+            U256::from(config.decimals.get()),
+            config.symbol.get_string(),
+        ))
+    }
+
+    // Every token ever listed or escrowed, with its status, amount held and number of open escrows
+    #[allow(clippy::type_complexity)]
+    pub fn get_token_totals(
+        &self,
+        offset: U256,
+        limit: U256,
+    ) -> Result<(Vec<Address>, Vec<U256>, Vec<U256>, Vec<U256>), Vec<u8>> {
+        let tokens = enumerable::page(&self.known_token_list, offset, limit);
+        let mut statuses = Vec::new();
+        let mut escrowed = Vec::new();
+        let mut live = Vec::new();
+        for token in &tokens {
+            statuses.push(U256::from(self.token_status.get(*token)));
+            escrowed.push(self.escrowed_amount.get(*token));
+            live.push(self.live_escrow_count.get(*token));
+        }
+        Ok((tokens, statuses, escrowed, live))
+    }
+
+    // Returns (tokens, total, paid, refunded) per token. Single-token escrows
+    // report their one token with the escrow-wide amounts.
+    #[allow(clippy::type_complexity)]
+    pub fn get_escrow_token_totals(
+        &self,
+        escrow_id: U256,
+    ) -> Result<(Vec<Address>, Vec<U256>, Vec<U256>, Vec<U256>), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let tokens = self.escrow_token_list(escrow_id);
+        let mut totals = Vec::new();
+        let mut paid = Vec::new();
+        let mut refunded = Vec::new();
+        if escrow.multi_token.get() {
+            let totals_map = self.escrow_token_totals.get(escrow_id);
+            for token in &tokens {
+                let token_totals = totals_map.get(*token);
+                totals.push(token_totals.total.get());
+                paid.push(token_totals.paid.get());
+                refunded.push(token_totals.refunded.get());
+            }
+        } else {
+            totals.push(escrow.total_amount.get());
+            paid.push(escrow.paid_amount.get());
+            refunded.push(escrow.refunded_amount.get());
+        }
+        Ok((tokens, totals, paid, refunded))
+    }
+
+    // Returns (escrow type, period interval, work started at, next period claimable at).
+    // The last value is zero for non-retainers and before work starts.
+    pub fn get_escrow_schedule(&self, escrow_id: U256) -> Result<(U256, U256, U256, U256), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let escrow_type = escrow.escrow_type.get();
+        let started_at = escrow.started_at.get();
+        let mut next_claimable = U256::ZERO;
+        if escrow_type == EscrowType::Retainer as u8 && escrow.work_started.get() {
+            let milestones_map = self.milestones.get(escrow_id);
+            let mut i = U256::ZERO;
+            while i < escrow.milestone_count.get() {
+                if milestones_map.get(i).status.get() == MilestoneStatus::NotStarted as u8 {
+                    next_claimable = self.period_claimable_at(escrow_id, i);
+                    break;
+                }
+                i += U256::from(1);
+            }
+        }
+        Ok((U256::from(escrow_type), escrow.period_interval.get(), started_at, next_claimable))
+    }
+
+    // Returns (start, end, vested, withdrawable)
+    pub fn get_stream(&self, escrow_id: U256) -> Result<(U256, U256, U256, U256), Vec<u8>> {
+        if !self.is_stream(escrow_id) {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+        let escrow = self.escrows.get(escrow_id);
+        Ok((
+            escrow.stream_start.get(),
+            escrow.stream_end.get(),
+            self.vested_amount(escrow_id),
+            self.withdrawable_amount(escrow_id),
+        ))
+    }
+
+    // Returns (hours, description hash, amount, status, submitted at)
+    pub fn get_time_log(&self, escrow_id: U256, log_index: U256) -> Result<(U256, B256, U256, U256, U256), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.escrow_type.get() != EscrowType::Hourly as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+        if log_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+        let time_logs = self.time_logs.get(escrow_id);
+        let log = time_logs.get(log_index);
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(log_index);
+        Ok((
+            log.hours.get(),
+            log.description_hash.get(),
+            milestone.amount.get(),
+            U256::from(milestone.status.get()),
+            milestone.submitted_at.get(),
+        ))
+    }
+
+    // Returns (hourly rate, budget, logged, remaining)
+    pub fn get_hourly_budget(&self, escrow_id: U256) -> Result<(U256, U256, U256, U256), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.escrow_type.get() != EscrowType::Hourly as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+        Ok((
+            escrow.hourly_rate.get(),
+            escrow.total_amount.get(),
+            escrow.logged_amount.get(),
+            self.remaining_hourly_budget(escrow_id),
+        ))
+    }
+
+    // Returns (is child, parent escrow id, parent milestone index)
+    pub fn get_parent_escrow(&self, escrow_id: U256) -> Result<(bool, U256, U256), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        Ok((escrow.is_child.get(), escrow.parent_id.get(), escrow.parent_milestone.get()))
+    }
+
+    pub fn get_child_escrows(&self, parent_id: U256) -> Result<Vec<U256>, Vec<u8>> {
+        let children = self.child_escrows.get(parent_id);
+        Ok((0..children.len()).filter_map(|i| children.get(i)).collect())
+    }
+
+    // Returns (amount held or spent by child escrows, child escrows still open,
+    // amount the depositor approved for subcontracting)
+    pub fn get_milestone_subcontracting(
+        &self,
+        escrow_id: U256,
+        milestone_index: U256,
+    ) -> Result<(U256, U256, U256), Vec<u8>> {
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        Ok((
+            milestone.subcontracted.get(),
+            milestone.open_children.get(),
+            milestone.subcontract_allowance.get(),
+        ))
+    }
+
+    // Returns (recipients, shares in basis points), both empty when the beneficiary takes it all
+    pub fn get_milestone_split(&self, escrow_id: U256, milestone_index: U256) -> Result<(Vec<Address>, Vec<U256>), Vec<u8>> {
+        Ok(self.milestone_split(escrow_id, milestone_index))
+    }
+
+    // Returns (incremental, funded amount, funded flag per milestone)
+    pub fn get_milestone_funding(&self, escrow_id: U256) -> Result<(bool, U256, Vec<bool>), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let mut funded = Vec::new();
+        let mut i = U256::ZERO;
+        while i < escrow.milestone_count.get() {
+            funded.push(self.is_milestone_funded(escrow_id, i));
+            i += U256::from(1);
+        }
+        Ok((escrow.incremental.get(), escrow.funded_amount.get(), funded))
+    }
+
+    // True while the user is a party to an escrow that is not yet closed
+    pub fn has_active_escrow(&self, user: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.open_escrow_count.get(user) > U256::ZERO)
+    }
+
+    pub fn get_whitelisted_token_count(&self) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.whitelisted_token_list.len()))
+    }
+
+    pub fn get_whitelisted_tokens(&self, offset: U256, limit: U256) -> Result<Vec<Address>, Vec<u8>> {
+        Ok(enumerable::page(&self.whitelisted_token_list, offset, limit))
+    }
+
+    pub fn get_arbiter_staking(&self) -> Result<(Address, U256, U256, U256), Vec<u8>> {
+        Ok((
+            self.arbiter_stake_token.get(),
+            self.min_arbiter_stake.get(),
+            self.unstake_cooldown.get(),
+            self.total_arbiter_stake.get(),
+        ))
+    }
+
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> Result<bool, Vec<u8>> {
+        Ok(forwarder != Address::ZERO && forwarder == self.trusted_forwarder.get())
+    }
+
+    // Next nonce a signer must use for a *_by_sig call
+    pub fn nonces(&self, signer: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.nonces.get(signer))
+    }
+
+    pub fn domain_separator(&self) -> Result<B256, Vec<u8>> {
+        Ok(self.domain_separator_internal())
+    }
+
+    pub fn permit2(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.permit2_address())
+    }
+
+    pub fn governance(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.governance.get())
+    }
+
+    pub fn arbiter_fee_balance(&self, arbiter: Address, token: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.arbiter_fee_balances.get(arbiter).get(token))
+    }
+
+    pub fn arbitration_fees_held(&self, token: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.arbitration_fees_held.get(token))
+    }
+
+    // Returns (ruling pending, ruled amount, appeal deadline, appealed, appellant, bond,
+    // appeal panel, appeal voting deadline)
+    #[allow(clippy::type_complexity)]
+    pub fn get_dispute_ruling(
+        &self,
+        escrow_id: U256,
+        milestone_index: U256,
+    ) -> Result<(bool, U256, U256, bool, Address, U256, Vec<Address>, U256), Vec<u8>> {
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let mut appeal_panel = Vec::new();
+        let mut i = 0;
+        while let Some(arbiter) = dispute.appeal_panel.get(i) {
+            appeal_panel.push(arbiter);
+            i += 1;
+        }
+        Ok((
+            dispute.ruling_pending.get(),
+            dispute.ruled_amount.get(),
+            dispute.ruled_at.get() + self.appeal_window.get(),
+            dispute.appealed.get(),
+            dispute.appellant.get(),
+            dispute.appeal_bond.get(),
+            appeal_panel,
+            dispute.appealed_at.get() + self.appeal_voting_period.get(),
+        ))
+    }
+
+    pub fn get_appeal_config(&self) -> Result<(U256, U256, U256), Vec<u8>> {
+        Ok((self.appeal_window.get(), self.appeal_bond_bps.get(), self.appeal_voting_period.get()))
+    }
+
+    pub fn get_panel_seed_block(&self, escrow_id: U256, milestone_index: U256) -> Result<U256, Vec<u8>> {
+        Ok(self.disputes.get(escrow_id).get(milestone_index).panel_seed_block.get())
+    }
+
+    pub fn get_dispute_votes(
+        &self,
+        escrow_id: U256,
+        milestone_index: U256,
+    ) -> Result<(Vec<Address>, Vec<U256>), Vec<u8>> {
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let mut voters = Vec::new();
+        let mut amounts = Vec::new();
+        let mut i = 0;
+        while let Some(voter) = dispute.voters.get(i) {
+            voters.push(voter);
+            amounts.push(dispute.vote_amounts.get(voter));
+            i += 1;
+        }
+        Ok((voters, amounts))
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn get_dispute(
+        &self,
+        escrow_id: U256,
+        milestone_index: U256,
+    ) -> Result<(Address, U256, U256, bool, U256, String), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        Ok((
+            milestone.disputed_by.get(),
+            milestone.disputed_at.get(),
+            dispute.response_deadline.get(),
+            dispute.counterparty_responded.get(),
+            U256::from(dispute.evidence.len()),
+            milestone.dispute_reason.get_string(),
+        ))
+    }
+
+    // Returns (submitters, content refs, notes, timestamps) in submission order
+    #[allow(clippy::type_complexity)]
+    pub fn get_evidence_page(
+        &self,
+        escrow_id: U256,
+        milestone_index: U256,
+        offset: U256,
+        limit: U256,
+    ) -> Result<(Vec<Address>, Vec<String>, Vec<String>, Vec<U256>), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        let disputes_map = self.disputes.get(escrow_id);
+        let dispute = disputes_map.get(milestone_index);
+        let count = U256::from(dispute.evidence.len());
+        let mut submitters = Vec::new();
+        let mut content_refs = Vec::new();
+        let mut notes = Vec::new();
+        let mut timestamps = Vec::new();
+        let mut i = offset;
+        while i < count && i < offset.saturating_add(limit) {
+            if let Some(entry) = dispute.evidence.getter(i.as_limbs()[0] as usize) {
+                submitters.push(entry.submitter.get());
+                content_refs.push(entry.content_ref.get_string());
+                notes.push(entry.note.get_string());
+                timestamps.push(entry.submitted_at.get());
+            }
+            i += U256::from(1);
+        }
+        Ok((submitters, content_refs, notes, timestamps))
+    }
+
+    // Open escrows on which `arbiter` still holds a seat, scanning `limit` entries
+    // of the arbiter's assignment history from `offset`. After a revocation these
+    // are the escrows that need a replacement.
+    pub fn get_affected_escrows(
+        &self,
+        arbiter: Address,
+        offset: U256,
+        limit: U256,
+    ) -> Result<Vec<U256>, Vec<u8>> {
+        let history = self.arbiter_escrows.get(arbiter);
+        let count = U256::from(history.len());
+        let mut affected = Vec::new();
+        let mut i = offset;
+        while i < count && i < offset.saturating_add(limit) {
+            if let Some(escrow_id) = history.get(i) {
+                if self.is_arbiter_for_escrow_internal(escrow_id, arbiter)
+                    && !Self::is_closed_status(self.escrows.get(escrow_id).status.get())
+                    && !affected.contains(&escrow_id)
+                {
+                    affected.push(escrow_id);
+                }
+            }
+            i += U256::from(1);
+        }
+        Ok(affected)
+    }
+
+    pub fn get_arbiter_escrow_count(&self, arbiter: Address) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.arbiter_escrows.get(arbiter).len()))
+    }
+
+    pub fn get_arbiter_replacement(&self, escrow_id: U256) -> Result<(Address, Address, Address), Vec<u8>> {
+        let proposal = self.arbiter_replacements.get(escrow_id);
+        Ok((proposal.old_arbiter.get(), proposal.new_arbiter.get(), proposal.proposed_by.get()))
+    }
+
+    pub fn get_replacement_seed_block(&self, escrow_id: U256, arbiter: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.replacement_seed_blocks.get(escrow_id).get(arbiter))
+    }
+
+    pub fn is_arbiter_for_escrow(&self, escrow_id: U256, arbiter: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.is_arbiter_for_escrow_internal(escrow_id, arbiter))
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn get_escrow_summary(&self, escrow_id: U256) -> Result<(Address, Address, Vec<Address>, U256, U256, U256, U256, Address, U256, bool, U256, U256, bool, String, String), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        let remaining = escrow.total_amount.get() - escrow.paid_amount.get() - escrow.refunded_amount.get();
+        let arbiters_list = self.escrow_arbiters(escrow_id);
+        Ok((
+            escrow.depositor.get(),
+            escrow.beneficiary.get(),
+            arbiters_list,
+            U256::from(escrow.status.get()), // Return as U256 (Stylus doesn't support U8 in return tuples)
+            escrow.total_amount.get(),
+            escrow.paid_amount.get(),
+            remaining,
+            escrow.token.get(),
+            escrow.deadline.get(),
+            escrow.work_started.get(),
+            escrow.created_at.get(),
+            escrow.milestone_count.get(),
+            escrow.is_open_job.get(),
+            escrow.project_title.get_string(),
+            escrow.project_description.get_string(),
+        ))
+    }
+
+    pub fn get_milestones(&self, _escrow_id: U256) -> Result<Vec<u8>, Vec<u8>> {
+        // Return empty vec - milestone serialization would need custom encoding
+        Ok(Vec::new())
+    }
+
+    pub fn get_user_escrows(&self, user: Address) -> Result<Vec<U256>, Vec<u8>> {
+        let escrows_vec = &self.user_escrows.get(user);
+        let mut escrows_list = Vec::new();
+        let mut i = 0;
+        while let Some(escrow_id) = escrows_vec.get(i) {
+            escrows_list.push(escrow_id);
+            i += 1;
+        }
+        Ok(escrows_list)
+    }
+
+    pub fn get_applications_page(
+        &self,
+        _escrow_id: U256,
+        _offset: U256,
+        _limit: U256,
+    ) -> Result<Vec<u8>, Vec<u8>> {
+        // Return empty vec - Application serialization would need custom encoding
+        Ok(Vec::new())
+    }
+
+    pub fn get_application_count(&self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        Ok(U256::from(self.escrow_applications.get(escrow_id).len()))
+    }
+
+    pub fn has_user_applied(&self, escrow_id: U256, user: Address) -> Result<bool, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+        Ok(self.has_applied.get(escrow_id).get(user))
+    }
+
+    pub fn get_reputation(&self, user: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.reputation.get(user))
+    }
+
+    pub fn get_completed_escrows(&self, user: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.completed_escrows.get(user))
+    }
+
+}
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    // Shared by every create_escrow* entrypoint. Not part of the public ABI:
+    // it trusts `depositor` and the funding mode chosen by the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_escrow_internal(
+        &mut self,
+        depositor: Address,
+        beneficiary: Address,
+        arbiters: Vec<Address>,
+        required_confirmations: u8,
+        milestone_amounts: Vec<U256>,
+        milestone_descriptions: Vec<String>,
+        token: Address,
+        duration: U256,
+        project_title: String,
+        project_description: String,
+        funding: Funding,
+    ) -> Result<U256, Vec<u8>> {
+        // Check if it's an open job (beneficiary is zero address)
+        let is_open_job = beneficiary == Address::ZERO;
+        
+        // Only check beneficiary != depositor for non-open jobs
+        // For open jobs, beneficiary is zero address which is allowed
+        if !is_open_job && beneficiary == depositor {
+            return Err(Error::BeneficiaryEqualsDepositor(String::new()).into());
+        }
+
+        let min_duration = self.min_duration.get();
+        let max_duration = self.max_duration.get();
+        // Defensive check: if constants are zero (uninitialized), use defaults
+        let min_dur = if min_duration == U256::ZERO { U256::from(3600) } else { min_duration };
+        let max_dur = if max_duration == U256::ZERO { U256::from(31536000) } else { max_duration };
+        if duration < min_dur || duration > max_dur {
+            return Err(Error::InvalidDuration(String::new()).into());
+        }
+
+        let max_milestones = self.max_milestones.get();
+        let max_milestones_count = if max_milestones == U256::ZERO { 20usize } else { max_milestones.as_limbs()[0] as usize };
+        // Hourly escrows start without milestones, time logs are appended later
+        let hourly_budget = match funding {
+            Funding::Hourly { budget } => Some(budget),
+            _ => None,
+        };
+        match hourly_budget {
+            Some(_) if !milestone_amounts.is_empty() => {
+                return Err(Error::MilestoneCountMismatch(String::new()).into());
+            }
+            Some(budget) if budget == U256::ZERO => {
+                return Err(Error::InvalidAmount(String::new()).into());
+            }
+            None if milestone_amounts.is_empty() => {
+                return Err(Error::EmptyMilestones(String::new()).into());
+            }
+            _ => {}
+        }
+        if milestone_amounts.len() > max_milestones_count {
+            return Err(Error::TooManyMilestones(String::new()).into());
+        }
+
+        if milestone_amounts.len() != milestone_descriptions.len() {
+            return Err(Error::MilestoneCountMismatch(String::new()).into());
+        }
+
+        if project_title.is_empty() {
+            return Err(Error::EmptyProjectTitle(String::new()).into());
+        }
+
+        // is_open_job already determined above
+        let mut total_amount = hourly_budget.unwrap_or(U256::ZERO);
+        for amount in &milestone_amounts {
+            if *amount == U256::ZERO {
+                return Err(Error::ZeroMilestoneAmount(String::new()).into());
+            }
+            total_amount += *amount;
+        }
+
+        let multi_token = matches!(funding, Funding::MultiToken(_));
+        let milestone_tokens = match &funding {
+            Funding::MultiToken(tokens) => {
+                if tokens.len() != milestone_amounts.len() {
+                    return Err(Error::TokenCountMismatch(String::new()).into());
+                }
+                tokens.clone()
+            }
+            _ => vec![token; milestone_amounts.len()],
+        };
+        // Total owed per distinct token, in first-seen order
+        let mut token_totals: Vec<(Address, U256)> = Vec::new();
+        for (milestone_token, amount) in milestone_tokens.iter().zip(&milestone_amounts) {
+            match token_totals.iter_mut().find(|(t, _)| t == milestone_token) {
+                Some((_, sum)) => *sum += *amount,
+                None => token_totals.push((*milestone_token, *amount)),
+            }
+        }
+        if let Some(budget) = hourly_budget {
+            token_totals.push((token, budget));
+        }
+        // Child escrows spend funds the parent already deposited
+        let from_parent = matches!(funding, Funding::Parent);
+        if !from_parent {
+            for (deposit_token, amount) in &token_totals {
+                self.ensure_accepts_deposits(*deposit_token)?;
+                self.enforce_token_limits(*deposit_token, *amount)?;
+            }
+        }
+
+        let incremental = matches!(funding, Funding::Deferred);
+        match funding {
+            Funding::MultiToken(_) => {
+                let native_total = token_totals
+                    .iter()
+                    .find(|(t, _)| *t == Address::ZERO)
+                    .map_or(U256::ZERO, |(_, sum)| *sum);
+                if msg::value() != native_total {
+                    return Err(Error::ValueMismatch(String::new()).into());
+                }
+                for (deposit_token, amount) in &token_totals {
+                    if *deposit_token != Address::ZERO {
+                        self.transfer_in(*deposit_token, depositor, *amount)?;
+                    }
+                }
+            }
+            Funding::Native => {
+                let sent_value = msg::value();
+                if sent_value != total_amount {
+                    return Err(Error::ValueMismatch(String::new()).into());
+                }
+            }
+            Funding::Hourly { .. } if token == Address::ZERO => {
+                if msg::value() != total_amount {
+                    return Err(Error::ValueMismatch(String::new()).into());
+                }
+            }
+            Funding::Deferred | Funding::Parent => {}
+            // Native escrows can only be funded with msg.value
+            _ if token == Address::ZERO => {
+                return Err(Error::ValueMismatch(String::new()).into());
+            }
+            Funding::Allowance | Funding::Hourly { .. } => {
+                self.transfer_in(token, depositor, total_amount)?;
+            }
+            Funding::Permit2 { nonce, deadline, signature } => {
+                self.transfer_in_with_permit2(token, depositor, total_amount, nonce, deadline, &signature)?;
+            }
+        }
+        let funded_amount = if incremental { U256::ZERO } else { total_amount };
+        if !incremental && !from_parent {
+            for (deposit_token, amount) in &token_totals {
+                let current = self.escrowed_amount.get(*deposit_token);
+                self.escrowed_amount.setter(*deposit_token).set(current + *amount);
+            }
+        }
+
+        let escrow_id = self.next_escrow_id.get();
+        self.next_escrow_id.set(escrow_id + U256::from(1));
+        let deadline = U256::from(block::timestamp()) + duration;
+
+        let mut escrow = self.escrows.setter(escrow_id);
+        escrow.depositor.set(depositor);
+        escrow.beneficiary.set(beneficiary);
+        // Arbiters are kept both as a list and in the escrow_arbiter_member index
+        escrow.auto_arbiters.set(arbiters.is_empty());
+        let arbiters_vec = &mut escrow.arbiters;
+        let mut members = self.escrow_arbiter_member.setter(escrow_id);
+        for arbiter in &arbiters {
+            arbiters_vec.push(*arbiter);
+            members.setter(*arbiter).set(true);
+        }
+        drop(members);
+        escrow.required_confirmations.set(U8::from(required_confirmations));
+        // Multi-token escrows report their first milestone's token here
+        escrow.token.set(milestone_tokens.first().copied().unwrap_or(token));
+        escrow.multi_token.set(multi_token);
+        if multi_token {
+            for (deposit_token, _) in &token_totals {
+                escrow.tokens.push(*deposit_token);
+            }
+        }
+        escrow.total_amount.set(total_amount);
+        escrow.paid_amount.set(U256::ZERO);
+        escrow.refunded_amount.set(U256::ZERO);
+        escrow.incremental.set(incremental);
+        escrow.funded_amount.set(funded_amount);
+        escrow.open_disputes.set(U256::ZERO);
+        // Multi-token escrows only take the basis-point fee, see set_arbitration_fee
+        let default_fee = if multi_token { U256::ZERO } else { self.default_arbitration_fee.get() };
+        escrow.arbitration_fee.set(default_fee);
+        escrow.arbitration_fee_bps.set(self.default_arbitration_fee_bps.get());
+        escrow.deadline.set(deadline);
+        escrow.status.set(U8::from(EscrowStatus::Pending as u8));
+        escrow.work_started.set(false);
+        escrow.created_at.set(U256::from(block::timestamp()));
+        escrow.milestone_count.set(U256::from(milestone_amounts.len()));
+        escrow.is_open_job.set(is_open_job);
+        escrow.project_title.0.set_bytes(project_title.as_bytes());
+        escrow.project_description.0.set_bytes(project_description.as_bytes());
+        drop(escrow); // Drop escrow reference before accessing milestones
+
+        // Create milestones
+        let mut milestones_map = self.milestones.setter(escrow_id);
+        for i in 0..milestone_amounts.len() {
+            let amount = milestone_amounts[i];
+            let description = &milestone_descriptions[i];
+            let mut milestone = milestones_map.setter(U256::from(i));
+            milestone.amount.set(amount);
+            milestone.description.0.set_bytes(description.as_bytes());
+            milestone.status.set(U8::from(MilestoneStatus::NotStarted as u8));
+            milestone.submitted_at.set(U256::ZERO);
+            milestone.approved_at.set(U256::ZERO);
+            milestone.disputed_at.set(U256::ZERO);
+            milestone.disputed_by.set(Address::ZERO);
+            milestone.dispute_reason.0.set_bytes([]);
+            milestone.funded.set(!incremental);
+            if multi_token {
+                milestone.token.set(milestone_tokens[i]);
+            }
+        }
+        drop(milestones_map);
+
+        if multi_token {
+            let mut totals_map = self.escrow_token_totals.setter(escrow_id);
+            for (deposit_token, amount) in &token_totals {
+                totals_map.setter(*deposit_token).total.set(*amount);
+            }
+        }
+
+        for arbiter in arbiters {
+            self.arbiter_escrows.setter(arbiter).push(escrow_id);
+        }
+
+        for (deposit_token, _) in &token_totals {
+            self.track_live_escrow(*deposit_token);
+        }
+
+        // Add to user escrows
+        let mut user_escrows = self.user_escrows.setter(depositor);
+        user_escrows.push(escrow_id);
+        drop(user_escrows);
+        self.track_open_escrow(depositor);
+        if !is_open_job {
+            let mut user_escrows_beneficiary = self.user_escrows.setter(beneficiary);
+            user_escrows_beneficiary.push(escrow_id);
+            drop(user_escrows_beneficiary);
+            self.track_open_escrow(beneficiary);
+        }
+
+        Ok(escrow_id)
+    }
+
+    pub fn start_work_internal(&mut self, caller: Address, escrow_id: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+        
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if caller != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if escrow.status.get() != EscrowStatus::Pending as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if escrow.work_started.get() {
+            return Err(Error::WorkNotStarted(String::new()).into());
+        }
+
+        let retainer_length = escrow.period_interval.get().saturating_mul(escrow.milestone_count.get());
+        let is_retainer = escrow.escrow_type.get() == EscrowType::Retainer as u8;
+        drop(escrow);
+
+        let now = U256::from(block::timestamp());
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.work_started.set(true);
+        escrow_mut.started_at.set(now);
+        escrow_mut.status.set(U8::from(EscrowStatus::InProgress as u8));
+        // A retainer's schedule runs from the start of work
+        if is_retainer {
+            escrow_mut.deadline.set(now.saturating_add(retainer_length));
+        }
+
+        Ok(())
+    }
+
+    pub fn submit_milestone_internal(
+        &mut self,
+        caller: Address,
+        escrow_id: U256,
+        milestone_index: U256,
+        description: String,
+    ) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if caller != escrow.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if !Self::is_active_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if escrow.escrow_type.get() != EscrowType::Milestone as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::NotStarted as u8 {
+            return Err(Error::AlreadySubmitted(String::new()).into());
+        }
+
+        if !self.is_milestone_funded(escrow_id, milestone_index) {
+            return Err(Error::MilestoneNotFunded(String::new()).into());
+        }
+
+        if milestone.open_children.get() > U256::ZERO {
+            return Err(Error::ChildEscrowsOpen(String::new()).into());
+        }
+
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Submitted as u8));
+        milestone_mut.submitted_at.set(U256::from(block::timestamp()));
+        if !description.is_empty() {
+            milestone_mut.description.0.set_bytes(description.as_bytes());
+        }
+
+        Ok(())
+    }
+
+    pub fn approve_milestone_internal(&mut self, caller: Address, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+
+        let escrow = self.escrows.get(escrow_id);
+        if escrow.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if caller != escrow.depositor.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if !Self::is_active_status(escrow.status.get()) {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if milestone_index >= escrow.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::Submitted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        // Subcontracted work has to settle before the parent milestone pays out
+        if milestone.open_children.get() > U256::ZERO {
+            return Err(Error::ChildEscrowsOpen(String::new()).into());
+        }
+        drop(milestone);
+        drop(milestones_map);
+        drop(escrow);
+
+        self.release_milestone(escrow_id, milestone_index)
+    }
+
+    // Pay a milestone out to the beneficiary and settle the escrow once nothing is left
+    pub fn release_milestone(&mut self, escrow_id: U256, milestone_index: U256) -> Result<(), Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let milestones_map = self.milestones.get(escrow_id);
+        let milestone = milestones_map.get(milestone_index);
+        let amount = milestone.amount.get();
+        // Child escrows already paid their share out of this milestone
+        let payout = amount - milestone.subcontracted.get();
+        drop(milestone);
+        drop(milestones_map);
+        let token = self.milestone_token(escrow_id, milestone_index);
+        let beneficiary = escrow.beneficiary.get();
+        let depositor = escrow.depositor.get();
+        let total = escrow.total_amount.get();
+        let refunded = escrow.refunded_amount.get();
+        let paid = escrow.paid_amount.get();
+        let new_paid = paid + amount;
+        
+        let mut milestones_map_mut = self.milestones.setter(escrow_id);
+        let mut milestone_mut = milestones_map_mut.setter(milestone_index);
+        milestone_mut.status.set(U8::from(MilestoneStatus::Approved as u8));
+        milestone_mut.approved_at.set(U256::from(block::timestamp()));
+        drop(milestone_mut);
+        drop(milestones_map_mut);
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        escrow_mut.paid_amount.set(new_paid);
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed - payout);
+        drop(escrow_mut);
+        self.book_token_settlement(escrow_id, token, amount, U256::ZERO);
+        
+        if payout > U256::ZERO {
+            self.pay_beneficiary(escrow_id, milestone_index, token, payout)?;
+        }
+
+        if self.meets_rep_threshold(escrow_id, token) {
+            let rep_points = self.reputation_per_milestone.get();
+            self.credit_milestone_reputation(escrow_id, milestone_index, rep_points);
+        }
+
+        if new_paid + refunded == total {
+            self.close_escrow(escrow_id, EscrowStatus::Released);
+        }
+
+        if new_paid == total {
+            let rep_eligible = self
+                .escrow_token_list(escrow_id)
+                .into_iter()
+                .any(|escrow_token| self.meets_rep_threshold(escrow_id, escrow_token));
+            if rep_eligible {
+                let rep_points = self.reputation_per_escrow.get();
+                self.update_reputation(beneficiary, rep_points);
+                self.update_reputation(depositor, rep_points);
+            }
+            let completed_beneficiary = self.completed_escrows.get(beneficiary);
+            self.completed_escrows.setter(beneficiary).set(completed_beneficiary + U256::from(1));
+            let completed_depositor = self.completed_escrows.get(depositor);
+            self.completed_escrows.setter(depositor).set(completed_depositor + U256::from(1));
+        }
+
+        Ok(())
+    }
 }
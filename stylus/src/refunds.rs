@@ -0,0 +1,119 @@
+//! Refund helpers for SecureFlow
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use alloy_primitives::{Address, U256, U8};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowStatus, MilestoneStatus};
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    // Refund every milestone the beneficiary can no longer claim.
+    // NotStarted milestones are always refundable, Rejected ones only when
+    // `include_rejected` is set. Submitted and Disputed milestones stay frozen
+    // until they are approved or resolved.
+    pub fn refund_open_milestones(
+        &mut self,
+        escrow_id: U256,
+        include_rejected: bool,
+        terminal_status: EscrowStatus,
+    ) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let milestone_count = escrow.milestone_count.get();
+        let incremental = escrow.incremental.get();
+        drop(escrow);
+
+        // (token, refunded, paid back) per token, a single entry unless the escrow is multi-token
+        let mut refunds: Vec<(Address, U256, U256)> = Vec::new();
+        let mut refund_amount = U256::ZERO;
+        let mut frozen = false;
+        let mut i = U256::ZERO;
+        while i < milestone_count {
+            let token = self.milestone_token(escrow_id, i);
+            let mut milestones_map = self.milestones.setter(escrow_id);
+            let mut milestone = milestones_map.setter(i);
+            let status = milestone.status.get();
+            let refundable = status == MilestoneStatus::NotStarted as u8
+                || (include_rejected && status == MilestoneStatus::Rejected as u8);
+            if refundable && milestone.open_children.get() > U256::ZERO {
+                // Funds held by child escrows come back once they settle
+                frozen = true;
+            } else if refundable {
+                let amount = milestone.amount.get();
+                // Unfunded milestones are cancelled without paying anything back,
+                // and what child escrows spent is gone
+                let payout = if incremental && !milestone.funded.get() {
+                    U256::ZERO
+                } else {
+                    amount - milestone.subcontracted.get()
+                };
+                milestone.status.set(U8::from(MilestoneStatus::Refunded as u8));
+                refund_amount += amount;
+                match refunds.iter_mut().find(|(t, _, _)| *t == token) {
+                    Some((_, refunded, paid_back)) => {
+                        *refunded += amount;
+                        *paid_back += payout;
+                    }
+                    None => refunds.push((token, amount, payout)),
+                }
+            } else if status == MilestoneStatus::Submitted as u8 || status == MilestoneStatus::Disputed as u8 {
+                frozen = true;
+            }
+            i += U256::from(1);
+        }
+
+        if refund_amount == U256::ZERO {
+            if frozen {
+                return Err(Error::MilestoneFrozen(String::new()).into());
+            }
+            return Err(Error::NothingToRefund(String::new()).into());
+        }
+
+        for (token, refunded, paid_back) in refunds {
+            self.record_refund(escrow_id, token, refunded, paid_back, terminal_status)?;
+        }
+
+        Ok(refund_amount)
+    }
+
+    // Book `refund_amount` of milestones already marked Refunded, pay `payout`
+    // of it back to the depositor, and close the escrow with `terminal_status`
+    // when nothing is left
+    pub fn record_refund(
+        &mut self,
+        escrow_id: U256,
+        token: Address,
+        refund_amount: U256,
+        payout: U256,
+        terminal_status: EscrowStatus,
+    ) -> Result<(), Vec<u8>> {
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        let refunded = escrow_mut.refunded_amount.get() + refund_amount;
+        escrow_mut.refunded_amount.set(refunded);
+        let settled = escrow_mut.paid_amount.get() + refunded == escrow_mut.total_amount.get();
+        drop(escrow_mut);
+        self.book_token_settlement(escrow_id, token, U256::ZERO, refund_amount);
+        if settled {
+            self.close_escrow(escrow_id, terminal_status);
+        }
+
+        if payout == U256::ZERO {
+            return Ok(());
+        }
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed - payout);
+        self.refund_depositor(escrow_id, token, payout)
+    }
+
+    // Every payment back to an escrow's depositor goes through here
+    pub fn refund_depositor(&mut self, escrow_id: U256, token: Address, amount: U256) -> Result<(), Vec<u8>> {
+        if self.escrows.get(escrow_id).is_child.get() {
+            self.return_to_parent(escrow_id, token, amount);
+            return Ok(());
+        }
+        let depositor = self.escrows.get(escrow_id).depositor.get();
+        self.transfer_out(token, depositor, amount)
+    }
+}
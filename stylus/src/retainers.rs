@@ -0,0 +1,133 @@
+//! Retainer escrow helpers for SecureFlow
+//!
+//! A retainer funds a fixed number of equal periods, stored as milestones.
+//! Period `i` becomes claimable by the beneficiary `(i + 1) * period_interval`
+//! after work starts. Until then the depositor can dispute it or cancel the
+//! retainer, which refunds every period that has not elapsed yet.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::block;
+use alloy_primitives::{U256, U8};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowStatus, EscrowType, MilestoneStatus};
+
+// When period `period_index` of a retainer started at `started_at` becomes claimable
+pub fn claimable_at(started_at: U256, period_interval: U256, period_index: U256) -> U256 {
+    started_at + (period_index + U256::from(1)) * period_interval
+}
+
+// A period elapses at its claimable time, never before work starts
+pub fn period_elapsed(work_started: bool, started_at: U256, period_interval: U256, period_index: U256, now: U256) -> bool {
+    work_started && now >= claimable_at(started_at, period_interval, period_index)
+}
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    pub fn is_retainer(&self, escrow_id: U256) -> bool {
+        self.escrows.get(escrow_id).escrow_type.get() == EscrowType::Retainer as u8
+    }
+
+    pub fn period_claimable_at(&self, escrow_id: U256, period_index: U256) -> U256 {
+        let escrow = self.escrows.get(escrow_id);
+        claimable_at(escrow.started_at.get(), escrow.period_interval.get(), period_index)
+    }
+
+    pub fn is_period_elapsed(&self, escrow_id: U256, period_index: U256) -> bool {
+        let escrow = self.escrows.get(escrow_id);
+        period_elapsed(
+            escrow.work_started.get(),
+            escrow.started_at.get(),
+            escrow.period_interval.get(),
+            period_index,
+            U256::from(block::timestamp()),
+        )
+    }
+
+    // The depositor may only dispute a period that has not become claimable yet
+    pub fn ensure_period_disputable(&self, escrow_id: U256, period_index: U256) -> Result<(), Vec<u8>> {
+        let status = self.milestones.get(escrow_id).get(period_index).status.get();
+        if status != MilestoneStatus::NotStarted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        if !self.escrows.get(escrow_id).work_started.get() || self.is_period_elapsed(escrow_id, period_index) {
+            return Err(Error::DisputePeriodExpired(String::new()).into());
+        }
+        Ok(())
+    }
+
+    // Refund every open period that has not elapsed. Elapsed but unclaimed
+    // periods stay claimable by the beneficiary.
+    pub fn refund_future_periods(&mut self, escrow_id: U256, terminal_status: EscrowStatus) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let period_count = escrow.milestone_count.get();
+        let token = escrow.token.get();
+        drop(escrow);
+
+        let mut refund_amount = U256::ZERO;
+        let mut i = U256::ZERO;
+        while i < period_count {
+            let elapsed = self.is_period_elapsed(escrow_id, i);
+            let mut milestones_map = self.milestones.setter(escrow_id);
+            let mut period = milestones_map.setter(i);
+            if !elapsed && period.status.get() == MilestoneStatus::NotStarted as u8 {
+                refund_amount += period.amount.get();
+                period.status.set(U8::from(MilestoneStatus::Refunded as u8));
+            }
+            i += U256::from(1);
+        }
+
+        if refund_amount == U256::ZERO {
+            return Err(Error::NothingToRefund(String::new()).into());
+        }
+
+        self.record_refund(escrow_id, token, refund_amount, refund_amount, terminal_status)?;
+        Ok(refund_amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STARTED_AT: u64 = 1_700_000_000;
+    const WEEK: u64 = 7 * 86_400;
+
+    fn elapsed(work_started: bool, period_index: u64, now: u64) -> bool {
+        period_elapsed(work_started, U256::from(STARTED_AT), U256::from(WEEK), U256::from(period_index), U256::from(now))
+    }
+
+    #[test]
+    fn each_period_is_claimable_one_interval_after_the_previous() {
+        let at = |i: u64| claimable_at(U256::from(STARTED_AT), U256::from(WEEK), U256::from(i));
+        assert_eq!(at(0), U256::from(STARTED_AT + WEEK));
+        assert_eq!(at(1), U256::from(STARTED_AT + 2 * WEEK));
+        assert_eq!(at(11), U256::from(STARTED_AT + 12 * WEEK));
+    }
+
+    #[test]
+    fn the_last_period_lands_on_the_retainer_deadline() {
+        // start_work sets the deadline to started_at + interval * period count
+        let period_count = 4;
+        let deadline = U256::from(STARTED_AT) + U256::from(WEEK) * U256::from(period_count);
+        assert_eq!(claimable_at(U256::from(STARTED_AT), U256::from(WEEK), U256::from(period_count - 1)), deadline);
+    }
+
+    #[test]
+    fn a_period_elapses_exactly_at_its_claimable_time() {
+        assert!(!elapsed(true, 0, STARTED_AT));
+        assert!(!elapsed(true, 0, STARTED_AT + WEEK - 1));
+        assert!(elapsed(true, 0, STARTED_AT + WEEK));
+        assert!(!elapsed(true, 1, STARTED_AT + WEEK));
+        assert!(elapsed(true, 1, STARTED_AT + 2 * WEEK));
+    }
+
+    #[test]
+    fn no_period_elapses_before_work_starts() {
+        // started_at is zero until start_work, so every claimable time is in the past
+        assert!(!period_elapsed(false, U256::ZERO, U256::from(WEEK), U256::ZERO, U256::from(STARTED_AT)));
+        assert!(!elapsed(false, 0, STARTED_AT + 52 * WEEK));
+    }
+}
@@ -0,0 +1,106 @@
+//! EIP-712 signature verification for relayed SecureFlow calls
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::{
+    block,
+    call::{self, Call},
+    contract,
+    crypto,
+};
+use alloy_primitives::{address, Address, B256, U256};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+
+// ecrecover precompile
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+// secp256k1n / 2, signatures with a higher s are malleable duplicates
+const HALF_CURVE_ORDER: U256 = U256::from_limbs([
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+]);
+
+pub const START_WORK_TYPE: &[u8] = b"StartWork(uint256 escrowId,uint256 nonce,uint256 deadline)";
+pub const SUBMIT_MILESTONE_TYPE: &[u8] =
+    b"SubmitMilestone(uint256 escrowId,uint256 milestoneIndex,string description,uint256 nonce,uint256 deadline)";
+pub const APPROVE_MILESTONE_TYPE: &[u8] =
+    b"ApproveMilestone(uint256 escrowId,uint256 milestoneIndex,uint256 nonce,uint256 deadline)";
+
+// Concatenate 32-byte words into an EIP-712 struct hash
+pub fn hash_struct(type_string: &[u8], fields: &[B256]) -> B256 {
+    let mut preimage = Vec::with_capacity(32 * (fields.len() + 1));
+    preimage.extend_from_slice(crypto::keccak(type_string).as_slice());
+    for field in fields {
+        preimage.extend_from_slice(field.as_slice());
+    }
+    crypto::keccak(preimage)
+}
+
+pub fn word(value: U256) -> B256 {
+    B256::from(value.to_be_bytes::<32>())
+}
+
+impl SecureFlow {
+    pub fn domain_separator_internal(&self) -> B256 {
+        let mut preimage = Vec::with_capacity(5 * 32);
+        preimage.extend_from_slice(
+            crypto::keccak(b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)").as_slice(),
+        );
+        preimage.extend_from_slice(crypto::keccak(b"SecureFlow").as_slice());
+        preimage.extend_from_slice(crypto::keccak(b"1").as_slice());
+        preimage.extend_from_slice(&U256::from(block::chainid()).to_be_bytes::<32>());
+        preimage.extend_from_slice(B256::left_padding_from(contract::address().as_slice()).as_slice());
+        crypto::keccak(preimage)
+    }
+
+    pub fn recover_signer(&self, digest: B256, v: u8, r: B256, s: B256) -> Option<Address> {
+        if (v != 27 && v != 28) || U256::from_be_bytes(s.0) > HALF_CURVE_ORDER {
+            return None;
+        }
+        let mut calldata = Vec::with_capacity(128);
+        calldata.extend_from_slice(digest.as_slice());
+        calldata.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+        calldata.extend_from_slice(r.as_slice());
+        calldata.extend_from_slice(s.as_slice());
+        let output = call::static_call(Call::new(), ECRECOVER, &calldata).ok()?;
+        if output.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_slice(&output[12..]);
+        if signer == Address::ZERO { None } else { Some(signer) }
+    }
+
+    // Check that `signer` signed `struct_hash` before `deadline`, then burn their nonce.
+    // The struct hash must already include the signer's current nonce.
+    pub fn use_signature(
+        &mut self,
+        signer: Address,
+        struct_hash: B256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        if U256::from(block::timestamp()) > deadline {
+            return Err(Error::SignatureExpired(String::new()).into());
+        }
+
+        let mut preimage = Vec::with_capacity(66);
+        preimage.extend_from_slice(&[0x19u8, 0x01u8]);
+        preimage.extend_from_slice(self.domain_separator_internal().as_slice());
+        preimage.extend_from_slice(struct_hash.as_slice());
+        let digest = crypto::keccak(preimage);
+
+        if self.recover_signer(digest, v, r, s) != Some(signer) {
+            return Err(Error::InvalidSignature(String::new()).into());
+        }
+
+        let nonce = self.nonces.get(signer);
+        self.nonces.setter(signer).set(nonce + U256::from(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! Milestone revenue splits for SecureFlow
+//!
+//! The beneficiary can attach a split table to any milestone that has not been
+//! paid or disputed. When the milestone is released or a ruling awards the
+//! beneficiary a share, the payout is divided by basis points across the
+//! table, and the per-milestone reputation follows the same shares. A stream
+//! is a single milestone, so its split applies to every withdrawal. Without a
+//! table everything goes to the beneficiary as before.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use alloy_primitives::{Address, U256};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::MilestoneStatus;
+
+pub const MAX_SPLIT_RECIPIENTS: usize = 10;
+
+// Every share is 1..=10000 bps and a non-empty table sums to exactly 10000.
+// An empty table clears the split
+pub fn validate_split(recipients: &[Address], shares_bps: &[U256]) -> Result<(), Vec<u8>> {
+    if recipients.len() != shares_bps.len() || recipients.len() > MAX_SPLIT_RECIPIENTS {
+        return Err(Error::InvalidSplit(String::new()).into());
+    }
+    let mut total_bps = U256::ZERO;
+    for (recipient, share) in recipients.iter().zip(shares_bps) {
+        if *recipient == Address::ZERO || *share == U256::ZERO || *share > U256::from(10_000) {
+            return Err(Error::InvalidSplit(String::new()).into());
+        }
+        total_bps += *share;
+    }
+    if !recipients.is_empty() && total_bps != U256::from(10_000) {
+        return Err(Error::InvalidSplit(String::new()).into());
+    }
+    Ok(())
+}
+
+// Divide `amount` by the shares, the last recipient takes the rounding dust
+pub fn split_amounts(amount: U256, shares_bps: &[U256]) -> Vec<U256> {
+    let mut remaining = amount;
+    let mut parts = Vec::with_capacity(shares_bps.len());
+    for (i, share) in shares_bps.iter().enumerate() {
+        let part = if i + 1 == shares_bps.len() {
+            remaining
+        } else {
+            amount * *share / U256::from(10_000)
+        };
+        remaining -= part;
+        parts.push(part);
+    }
+    parts
+}
+
+impl SecureFlow {
+    pub fn set_milestone_split_internal(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        recipients: Vec<Address>,
+        shares_bps: Vec<U256>,
+    ) -> Result<(), Vec<u8>> {
+        let status = self.milestones.get(escrow_id).get(milestone_index).status.get();
+        if status != MilestoneStatus::NotStarted as u8
+            && status != MilestoneStatus::Submitted as u8
+            && status != MilestoneStatus::Rejected as u8
+        {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        validate_split(&recipients, &shares_bps)?;
+
+        let mut splits_map = self.milestone_splits.setter(escrow_id);
+        let mut split = splits_map.setter(milestone_index);
+        while split.recipients.pop().is_some() {}
+        while split.shares_bps.pop().is_some() {}
+        for (recipient, share) in recipients.iter().zip(&shares_bps) {
+            split.recipients.push(*recipient);
+            split.shares_bps.push(*share);
+        }
+        Ok(())
+    }
+
+    // Returns (recipients, shares in basis points), both empty when unsplit
+    pub fn milestone_split(&self, escrow_id: U256, milestone_index: U256) -> (Vec<Address>, Vec<U256>) {
+        let splits_map = self.milestone_splits.get(escrow_id);
+        let split = splits_map.get(milestone_index);
+        let recipients = (0..split.recipients.len()).filter_map(|i| split.recipients.get(i)).collect();
+        let shares = (0..split.shares_bps.len()).filter_map(|i| split.shares_bps.get(i)).collect();
+        (recipients, shares)
+    }
+
+    // Pay the beneficiary's side of a milestone, the last recipient takes the rounding dust
+    pub fn pay_beneficiary(
+        &mut self,
+        escrow_id: U256,
+        milestone_index: U256,
+        token: Address,
+        amount: U256,
+    ) -> Result<(), Vec<u8>> {
+        let (recipients, shares) = self.milestone_split(escrow_id, milestone_index);
+        if recipients.is_empty() {
+            let beneficiary = self.escrows.get(escrow_id).beneficiary.get();
+            return self.transfer_out(token, beneficiary, amount);
+        }
+
+        for (recipient, part) in recipients.iter().zip(split_amounts(amount, &shares)) {
+            if part > U256::ZERO {
+                self.transfer_out(token, *recipient, part)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn credit_milestone_reputation(&mut self, escrow_id: U256, milestone_index: U256, points: U256) {
+        let (recipients, shares) = self.milestone_split(escrow_id, milestone_index);
+        if recipients.is_empty() {
+            let beneficiary = self.escrows.get(escrow_id).beneficiary.get();
+            self.update_reputation(beneficiary, points);
+            return;
+        }
+        for (recipient, share) in recipients.iter().zip(&shares) {
+            self.update_reputation(*recipient, points * *share / U256::from(10_000));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipients(count: u8) -> Vec<Address> {
+        (1..=count).map(Address::repeat_byte).collect()
+    }
+
+    fn bps(shares: &[u64]) -> Vec<U256> {
+        shares.iter().map(|share| U256::from(*share)).collect()
+    }
+
+    #[test]
+    fn accepts_tables_summing_to_the_whole() {
+        assert!(validate_split(&recipients(1), &bps(&[10_000])).is_ok());
+        assert!(validate_split(&recipients(3), &bps(&[5_000, 3_000, 2_000])).is_ok());
+        assert!(validate_split(&recipients(10), &bps(&[1_000; 10])).is_ok());
+    }
+
+    #[test]
+    fn an_empty_table_clears_the_split() {
+        assert!(validate_split(&[], &[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_shares() {
+        assert!(validate_split(&recipients(2), &bps(&[0, 10_000])).is_err());
+        assert!(validate_split(&recipients(2), &bps(&[10_001, 0])).is_err());
+        // Overflowing shares must not wrap around to a valid total
+        let wrapping = [U256::MAX, U256::from(10_001)].to_vec();
+        assert!(validate_split(&recipients(2), &wrapping).is_err());
+    }
+
+    #[test]
+    fn rejects_totals_other_than_the_whole() {
+        assert!(validate_split(&recipients(2), &bps(&[5_000, 4_999])).is_err());
+        assert!(validate_split(&recipients(2), &bps(&[5_000, 5_001])).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        assert!(validate_split(&recipients(2), &bps(&[10_000])).is_err());
+        assert!(validate_split(&[Address::ZERO], &bps(&[10_000])).is_err());
+        assert!(validate_split(&recipients(11), &bps(&[1_000; 11])).is_err());
+    }
+
+    #[test]
+    fn the_last_recipient_takes_the_rounding_dust() {
+        let parts = split_amounts(U256::from(1_001), &bps(&[3_333, 3_333, 3_334]));
+        assert_eq!(parts, bps(&[333, 333, 335]));
+        assert_eq!(split_amounts(U256::from(1_000), &bps(&[10_000])), bps(&[1_000]));
+    }
+}
@@ -1,10 +1,10 @@
 //! Storage definitions for SecureFlow
 
 use stylus_sdk::prelude::*;
-use crate::types::{EscrowData, Milestone, Application};
+use crate::types::{EscrowData, EscrowTokenTotals, Milestone, MilestoneSplit, TimeLog, Application, Dispute, ArbiterInfo, ArbiterReplacement, TokenConfig};
 
+// The entrypoint lives in forwarder.rs
 sol_storage! {
-    #[entrypoint]
     pub struct SecureFlow {
         // Constants
         uint256 min_duration;
@@ -17,21 +17,63 @@ sol_storage! {
         uint256 reputation_per_milestone;
         uint256 reputation_per_escrow;
         uint256 min_rep_eligible_escrow_value;
+        uint256 dispute_response_period;
+        uint256 max_evidence_per_dispute;
+        uint256 max_evidence_note_length;
+        uint256 unstake_cooldown;
+        uint256 appeal_window;
+        uint256 appeal_bond_bps;
+        uint256 appeal_voting_period;
         
         // Config
+        uint256 default_arbitration_fee;
+        uint256 default_arbitration_fee_bps;
         bool job_creation_paused;
         address owner;
+        address governance;
+        address permit2;
+        address trusted_forwarder; // ERC-2771 relayer allowed to append the real sender
+        mapping(address => uint256) nonces; // EIP-712 nonces for relayed calls
         bool paused;
         
         // State
         uint256 next_escrow_id;
         mapping(uint256 => EscrowData) escrows;
         mapping(uint256 => mapping(uint256 => Milestone)) milestones;
+        mapping(uint256 => mapping(uint256 => TimeLog)) time_logs;
+        mapping(uint256 => mapping(address => EscrowTokenTotals)) escrow_token_totals;
+        mapping(uint256 => mapping(uint256 => MilestoneSplit)) milestone_splits;
+        mapping(uint256 => uint256[]) child_escrows;
+        mapping(uint256 => mapping(uint256 => Dispute)) disputes;
+        mapping(uint256 => mapping(address => bool)) escrow_arbiter_member;
+        mapping(address => uint256[]) arbiter_escrows;
+        mapping(uint256 => ArbiterReplacement) arbiter_replacements;
+        mapping(uint256 => mapping(address => uint256)) replacement_seed_blocks; // pending replacement draws
         mapping(address => uint256[]) user_escrows;
+        mapping(address => uint256) open_escrow_count; // escrows the user is a party to that are not closed
         mapping(address => bool) authorized_arbiters;
-        mapping(address => bool) whitelisted_tokens;
+        mapping(address => bool) revoked_arbiters; // only the owner can authorize these again
+        address[] arbiter_pool;
+        mapping(address => uint256) arbiter_pool_index;
+        mapping(address => uint8) token_status;
+        address[] whitelisted_token_list;
+        mapping(address => uint256) whitelisted_token_index;
+        address[] known_token_list;
+        mapping(address => uint256) known_token_index;
+        mapping(address => uint256) live_escrow_count;
+        mapping(address => TokenConfig) token_configs;
         mapping(address => uint256) escrowed_amount;
+
+        // Arbitration fees, held outside escrowed_amount until claimed
+        mapping(address => uint256) arbitration_fees_held;
+        mapping(address => mapping(address => uint256)) arbiter_fee_balances;
         
+        // Arbiter staking
+        address arbiter_stake_token;
+        uint256 min_arbiter_stake;
+        uint256 total_arbiter_stake;
+        mapping(address => ArbiterInfo) arbiter_info;
+
         // Marketplace
         mapping(uint256 => Application[]) escrow_applications;
         mapping(uint256 => mapping(address => bool)) has_applied;
@@ -0,0 +1,129 @@
+//! Streaming escrow helpers for SecureFlow
+//!
+//! A stream vests its total linearly between `stream_start` and `stream_end`.
+//! Withdrawals are booked as `paid_amount` and a cancellation books the
+//! unvested remainder as `refunded_amount`, so vesting is capped at
+//! `total_amount - refunded_amount` once a stream is cancelled.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::block;
+use alloy_primitives::U256;
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowStatus, EscrowType};
+
+// Linear vesting of `total` over [start, end] at `now`, rounded down and capped
+// by whatever a cancellation already refunded
+pub fn vested_at(total: U256, refunded: U256, start: U256, end: U256, now: U256) -> U256 {
+    let vested = if now <= start {
+        U256::ZERO
+    } else if now >= end {
+        total
+    } else {
+        total * (now - start) / (end - start)
+    };
+    vested.min(total - refunded)
+}
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    pub fn is_stream(&self, escrow_id: U256) -> bool {
+        self.escrows.get(escrow_id).escrow_type.get() == EscrowType::Stream as u8
+    }
+
+    pub fn vested_amount(&self, escrow_id: U256) -> U256 {
+        let escrow = self.escrows.get(escrow_id);
+        let total = escrow.total_amount.get();
+        let start = escrow.stream_start.get();
+        let end = escrow.stream_end.get();
+        vested_at(total, escrow.refunded_amount.get(), start, end, U256::from(block::timestamp()))
+    }
+
+    pub fn withdrawable_amount(&self, escrow_id: U256) -> U256 {
+        self.vested_amount(escrow_id) - self.escrows.get(escrow_id).paid_amount.get()
+    }
+
+    pub fn withdraw_streamed_internal(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+        let amount = self.withdrawable_amount(escrow_id);
+        if amount == U256::ZERO {
+            return Err(Error::NothingToWithdraw(String::new()).into());
+        }
+
+        let mut escrow_mut = self.escrows.setter(escrow_id);
+        let paid = escrow_mut.paid_amount.get() + amount;
+        escrow_mut.paid_amount.set(paid);
+        let token = escrow_mut.token.get();
+        let settled = paid + escrow_mut.refunded_amount.get() == escrow_mut.total_amount.get();
+        drop(escrow_mut);
+        if settled {
+            self.close_escrow(escrow_id, EscrowStatus::Released);
+        }
+
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed - amount);
+        self.pay_beneficiary(escrow_id, U256::ZERO, token, amount)?;
+        Ok(amount)
+    }
+
+    // Stop the stream now and refund whatever has not vested
+    pub fn cancel_stream_internal(&mut self, escrow_id: U256) -> Result<U256, Vec<u8>> {
+        let escrow = self.escrows.get(escrow_id);
+        let token = escrow.token.get();
+        let unvested = escrow.total_amount.get() - escrow.refunded_amount.get() - self.vested_amount(escrow_id);
+        drop(escrow);
+        if unvested == U256::ZERO {
+            return Err(Error::NothingToRefund(String::new()).into());
+        }
+
+        self.record_refund(escrow_id, token, unvested, unvested, EscrowStatus::Refunded)?;
+        Ok(unvested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: u64 = 1_000;
+    const END: u64 = 1_000 + 30 * 86_400;
+
+    fn vested(total: u64, refunded: u64, now: u64) -> U256 {
+        vested_at(U256::from(total), U256::from(refunded), U256::from(START), U256::from(END), U256::from(now))
+    }
+
+    #[test]
+    fn nothing_vests_until_the_stream_starts() {
+        assert_eq!(vested(30_000, 0, 0), U256::ZERO);
+        assert_eq!(vested(30_000, 0, START), U256::ZERO);
+    }
+
+    #[test]
+    fn vests_linearly_between_start_and_end() {
+        assert_eq!(vested(30_000, 0, START + 86_400), U256::from(1_000));
+        assert_eq!(vested(30_000, 0, (START + END) / 2), U256::from(15_000));
+        assert_eq!(vested(30_000, 0, END - 86_400), U256::from(29_000));
+    }
+
+    #[test]
+    fn rounds_down_so_the_total_is_never_overpaid() {
+        // One second in, 7 / 2_592_000 of a unit has vested
+        assert_eq!(vested(7, 0, START + 1), U256::ZERO);
+        assert_eq!(vested(7, 0, END - 1), U256::from(6));
+    }
+
+    #[test]
+    fn everything_vests_at_the_end() {
+        assert_eq!(vested(30_000, 0, END), U256::from(30_000));
+        assert_eq!(vested(30_000, 0, END + 86_400), U256::from(30_000));
+    }
+
+    #[test]
+    fn a_cancelled_stream_stops_vesting() {
+        // Cancelled a third of the way in, the unvested 20_000 was refunded
+        let cancelled_at = START + 10 * 86_400;
+        assert_eq!(vested(30_000, 20_000, cancelled_at), U256::from(10_000));
+        assert_eq!(vested(30_000, 20_000, END), U256::from(10_000));
+    }
+}
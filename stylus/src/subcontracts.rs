@@ -0,0 +1,116 @@
+//! Child escrows for SecureFlow
+//!
+//! A beneficiary can subcontract part of a funded parent milestone by creating
+//! child escrows against it, acting as their depositor, up to the allowance the
+//! parent depositor approved for that milestone. Children cannot outlive the
+//! parent's deadline. The child amounts are
+//! booked as `subcontracted` on the parent milestone, which cannot be
+//! submitted or approved while any child is still open. Refunds from a child
+//! flow back into the parent milestone instead of leaving the contract, and
+//! whatever the children paid out is deducted from the parent's payout.
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use alloy_primitives::{Address, U256};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::{EscrowType, MilestoneStatus};
+
+#[allow(clippy::drop_non_drop)]
+impl SecureFlow {
+    // The milestone must be funded, not yet submitted, and have approved room for `amount`
+    pub fn ensure_can_subcontract(
+        &self,
+        caller: Address,
+        parent_id: U256,
+        milestone_index: U256,
+        amount: U256,
+    ) -> Result<(), Vec<u8>> {
+        let parent = self.escrows.get(parent_id);
+        if parent.depositor.get() == Address::ZERO {
+            return Err(Error::InvalidEscrow(String::new()).into());
+        }
+
+        if caller != parent.beneficiary.get() {
+            return Err(Error::Unauthorized(String::new()).into());
+        }
+
+        if !Self::is_active_status(parent.status.get()) || !parent.work_started.get() {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+
+        if parent.escrow_type.get() != EscrowType::Milestone as u8 {
+            return Err(Error::InvalidEscrowType(String::new()).into());
+        }
+
+        if milestone_index >= parent.milestone_count.get() {
+            return Err(Error::MilestoneNotFound(String::new()).into());
+        }
+        drop(parent);
+
+        if !self.is_milestone_funded(parent_id, milestone_index) {
+            return Err(Error::MilestoneNotFunded(String::new()).into());
+        }
+
+        let milestones_map = self.milestones.get(parent_id);
+        let milestone = milestones_map.get(milestone_index);
+        if milestone.status.get() != MilestoneStatus::NotStarted as u8 {
+            return Err(Error::InvalidStatus(String::new()).into());
+        }
+        if milestone.subcontracted.get().saturating_add(amount) > milestone.subcontract_allowance.get() {
+            return Err(Error::SubcontractNotApproved(String::new()).into());
+        }
+        Ok(())
+    }
+
+    pub fn link_child_escrow(&mut self, parent_id: U256, milestone_index: U256, child_id: U256, amount: U256) {
+        let mut child = self.escrows.setter(child_id);
+        child.is_child.set(true);
+        child.parent_id.set(parent_id);
+        child.parent_milestone.set(milestone_index);
+        drop(child);
+
+        let mut milestones_map = self.milestones.setter(parent_id);
+        let mut milestone = milestones_map.setter(milestone_index);
+        let subcontracted = milestone.subcontracted.get();
+        milestone.subcontracted.set(subcontracted + amount);
+        let open_children = milestone.open_children.get();
+        milestone.open_children.set(open_children + U256::from(1));
+        drop(milestone);
+        drop(milestones_map);
+
+        self.child_escrows.setter(parent_id).push(child_id);
+    }
+
+    // A child's refund stays in the contract and becomes payable by the parent milestone again
+    pub fn return_to_parent(&mut self, child_id: U256, token: Address, amount: U256) {
+        let child = self.escrows.get(child_id);
+        let parent_id = child.parent_id.get();
+        let milestone_index = child.parent_milestone.get();
+        drop(child);
+
+        let mut milestones_map = self.milestones.setter(parent_id);
+        let mut milestone = milestones_map.setter(milestone_index);
+        let subcontracted = milestone.subcontracted.get();
+        milestone.subcontracted.set(subcontracted - amount);
+        drop(milestone);
+        drop(milestones_map);
+
+        // The refund path already took it out of the escrowed total
+        let escrowed = self.escrowed_amount.get(token);
+        self.escrowed_amount.setter(token).set(escrowed + amount);
+    }
+
+    pub fn settle_child(&mut self, child_id: U256) {
+        let child = self.escrows.get(child_id);
+        let parent_id = child.parent_id.get();
+        let milestone_index = child.parent_milestone.get();
+        drop(child);
+
+        let mut milestones_map = self.milestones.setter(parent_id);
+        let mut milestone = milestones_map.setter(milestone_index);
+        let open_children = milestone.open_children.get();
+        milestone.open_children.set(open_children - U256::from(1));
+    }
+}
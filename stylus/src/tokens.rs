@@ -0,0 +1,111 @@
+//! Token whitelist configuration for SecureFlow
+
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::call::{self, Call};
+use alloy_primitives::{Address, U256, U8};
+use crate::storage::SecureFlow;
+use crate::errors::Error;
+use crate::types::TokenStatus;
+use crate::enumerable;
+
+impl SecureFlow {
+    // Cache decimals and symbol for display. Tokens that don't implement the
+    // optional metadata calls keep zero decimals and an empty symbol.
+    pub fn refresh_token_metadata_internal(&mut self, token: Address) {
+        let (decimals, symbol) = if token == Address::ZERO {
+            (18u8, String::from("ETH"))
+        } else {
+            let decimals = call::static_call(Call::new(), token, &[0x31u8, 0x3cu8, 0xe5u8, 0x67u8])
+                .ok()
+                .filter(|data| data.len() >= 32)
+                .map(|data| data[31])
+                .unwrap_or(0);
+            let symbol = call::static_call(Call::new(), token, &[0x95u8, 0xd8u8, 0x9bu8, 0x41u8])
+                .map(|data| Self::decode_symbol(&data))
+                .unwrap_or_default();
+            (decimals, symbol)
+        };
+
+        let mut config = self.token_configs.setter(token);
+        config.decimals.set(U8::from(decimals));
+        config.symbol.set_str(symbol);
+    }
+
+    // Accepts both ABI-encoded strings and the legacy bytes32 form
+    fn decode_symbol(data: &[u8]) -> String {
+        let bytes: &[u8] = if data.len() == 32 {
+            let end = data.iter().position(|b| *b == 0).unwrap_or(32);
+            &data[..end]
+        } else if data.len() >= 64 {
+            let len = U256::from_be_slice(&data[32..64]);
+            if len > U256::from(data.len() - 64) {
+                return String::new();
+            }
+            &data[64..64 + len.to::<usize>()]
+        } else {
+            return String::new();
+        };
+        String::from_utf8(bytes.to_vec()).unwrap_or_default()
+    }
+
+    // Per-token bounds on a single deposit and on the total held in escrow.
+    // Zero means no limit.
+    pub fn enforce_token_limits(&self, token: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let config = self.token_configs.get(token);
+        if amount < config.min_escrow.get() {
+            return Err(Error::BelowTokenMinimum(String::new()).into());
+        }
+
+        let max_escrow = config.max_escrow.get();
+        if max_escrow != U256::ZERO && amount > max_escrow {
+            return Err(Error::AboveTokenMaximum(String::new()).into());
+        }
+
+        self.enforce_token_cap(token, amount)
+    }
+
+    pub fn enforce_token_cap(&self, token: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let cap = self.token_configs.get(token).max_total_escrowed.get();
+        if cap != U256::ZERO && self.escrowed_amount.get(token) + amount > cap {
+            return Err(Error::TokenCapExceeded(String::new()).into());
+        }
+        Ok(())
+    }
+
+    // Native ETH is accepted unless the owner explicitly paused or deprecated it
+    pub fn ensure_accepts_deposits(&self, token: Address) -> Result<(), Vec<u8>> {
+        let status = self.token_status.get(token);
+        if status == TokenStatus::DepositsPaused as u8 {
+            return Err(Error::TokenDepositsPaused(String::new()).into());
+        }
+        if status == TokenStatus::Active as u8 || (token == Address::ZERO && status == TokenStatus::Unlisted as u8) {
+            return Ok(());
+        }
+        Err(Error::TokenNotWhitelisted(String::new()).into())
+    }
+
+    // Only Active tokens appear in the whitelist; every token ever listed or
+    // escrowed stays in the known list so its totals remain visible.
+    pub fn set_token_status_internal(&mut self, token: Address, status: TokenStatus) {
+        self.token_status.setter(token).set(U8::from(status as u8));
+        if status == TokenStatus::Active {
+            enumerable::insert(&mut self.whitelisted_token_list, &mut self.whitelisted_token_index, token);
+        } else {
+            enumerable::remove(&mut self.whitelisted_token_list, &mut self.whitelisted_token_index, token);
+        }
+        enumerable::insert(&mut self.known_token_list, &mut self.known_token_index, token);
+    }
+
+    pub fn track_live_escrow(&mut self, token: Address) {
+        enumerable::insert(&mut self.known_token_list, &mut self.known_token_index, token);
+        let live = self.live_escrow_count.get(token);
+        self.live_escrow_count.setter(token).set(live + U256::from(1));
+    }
+
+    pub fn untrack_live_escrow(&mut self, token: Address) {
+        let live = self.live_escrow_count.get(token);
+        self.live_escrow_count.setter(token).set(live - U256::from(1));
+    }
+}
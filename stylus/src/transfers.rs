@@ -7,10 +7,20 @@ use stylus_sdk::{
     call::{self, Call},
     contract,
 };
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{address, Address, B256, U256};
 use crate::storage::SecureFlow;
 use crate::errors::Error;
 
+// Canonical Permit2 deployment, same address on every chain
+const CANONICAL_PERMIT2: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+
+// Left-pad an address into a 32-byte ABI word
+fn address_word(addr: Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(addr.as_slice());
+    word
+}
+
 impl SecureFlow {
     pub fn transfer_out(&mut self, token: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
         if token == Address::ZERO {
@@ -23,8 +33,7 @@ impl SecureFlow {
             let transfer_selector = [0xa9u8, 0x05u8, 0x9cu8, 0xbbu8];
             let mut calldata = Vec::new();
             calldata.extend_from_slice(&transfer_selector);
-            calldata.extend_from_slice(&[0u8; 1]); // padding
-            calldata.extend_from_slice(to.as_slice());
+            calldata.extend_from_slice(&address_word(to));
             calldata.extend_from_slice(&amount.to_be_bytes::<32>());
             call::call(Call::new(), token, &calldata)?;
         }
@@ -40,8 +49,8 @@ impl SecureFlow {
         let transfer_from_selector = [0x23u8, 0xb8u8, 0x72u8, 0xddu8];
         let mut calldata = Vec::new();
         calldata.extend_from_slice(&transfer_from_selector);
-        calldata.extend_from_slice(from.as_slice());
-        calldata.extend_from_slice(contract::address().as_slice());
+        calldata.extend_from_slice(&address_word(from));
+        calldata.extend_from_slice(&address_word(contract::address()));
         calldata.extend_from_slice(&amount.to_be_bytes::<32>());
         match call::call(Call::new(), token, &calldata) {
             Ok(_) => Ok(()),
@@ -51,5 +60,78 @@ impl SecureFlow {
             }
         }
     }
-}
 
+    // EIP-2612 permit granting this contract `amount` of `owner`'s tokens.
+    // A failed permit is ignored: the signature may already have been used by
+    // a front-runner, and the following transferFrom fails anyway if the
+    // allowance is missing.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_permit(
+        &mut self,
+        token: Address,
+        owner: Address,
+        amount: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) {
+        let permit_selector = [0xd5u8, 0x05u8, 0xacu8, 0xcfu8];
+        let mut calldata = Vec::with_capacity(4 + 7 * 32);
+        calldata.extend_from_slice(&permit_selector);
+        calldata.extend_from_slice(&address_word(owner));
+        calldata.extend_from_slice(&address_word(contract::address()));
+        calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+        calldata.extend_from_slice(&deadline.to_be_bytes::<32>());
+        calldata.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+        calldata.extend_from_slice(r.as_slice());
+        calldata.extend_from_slice(s.as_slice());
+        let _ = call::call(Call::new(), token, &calldata);
+    }
+
+    pub fn permit2_address(&self) -> Address {
+        let permit2 = self.permit2.get();
+        if permit2 == Address::ZERO { CANONICAL_PERMIT2 } else { permit2 }
+    }
+
+    // Pull `amount` of `token` from `from` with a Permit2 signature transfer.
+    // The signed permit must name this contract as spender and cover `amount`.
+    pub fn transfer_in_with_permit2(
+        &mut self,
+        token: Address,
+        from: Address,
+        amount: U256,
+        nonce: U256,
+        deadline: U256,
+        signature: &[u8],
+    ) -> Result<(), Vec<u8>> {
+        // permitTransferFrom(((address,uint256),uint256,uint256),(address,uint256),address,bytes)
+        let permit_transfer_from_selector = [0x30u8, 0xf2u8, 0x8bu8, 0x7au8];
+        let padded_len = signature.len().div_ceil(32) * 32;
+        let mut calldata = Vec::with_capacity(4 + 9 * 32 + padded_len);
+        calldata.extend_from_slice(&permit_transfer_from_selector);
+        // PermitTransferFrom { permitted: { token, amount }, nonce, deadline }
+        calldata.extend_from_slice(&address_word(token));
+        calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+        calldata.extend_from_slice(&nonce.to_be_bytes::<32>());
+        calldata.extend_from_slice(&deadline.to_be_bytes::<32>());
+        // SignatureTransferDetails { to, requestedAmount }
+        calldata.extend_from_slice(&address_word(contract::address()));
+        calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+        calldata.extend_from_slice(&address_word(from));
+        // Offset of the signature bytes, right after the 8 head words
+        calldata.extend_from_slice(&U256::from(8 * 32).to_be_bytes::<32>());
+        calldata.extend_from_slice(&U256::from(signature.len()).to_be_bytes::<32>());
+        calldata.extend_from_slice(signature);
+        calldata.resize(4 + 9 * 32 + padded_len, 0);
+
+        let permit2 = self.permit2_address();
+        match call::call(Call::new(), permit2, &calldata) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                // Invalid or expired signature, reused nonce, or insufficient balance
+                Err(Error::InvalidAmount(String::new()).into())
+            }
+        }
+    }
+}
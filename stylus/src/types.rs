@@ -1,6 +1,10 @@
 //! Types and data structures for SecureFlow
 
+extern crate alloc;
+use alloc::vec::Vec;
+
 use stylus_sdk::prelude::*;
+use alloy_primitives::{Address, U256};
 
 // Note: SolidityType might not be available in this SDK version
 // Using repr(u8) for enums which is compatible
@@ -26,6 +30,36 @@ pub enum MilestoneStatus {
     Disputed = 3,
     Resolved = 4,
     Rejected = 5,
+    Refunded = 6,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TokenStatus {
+    Unlisted = 0,
+    Active = 1,
+    DepositsPaused = 2, // no new deposits, existing escrows unaffected
+    Deprecated = 3,     // delisted for good, existing escrows can still release and refund
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EscrowType {
+    Milestone = 0, // milestones released on approval
+    Retainer = 1,  // equal periods released on a fixed schedule
+    Stream = 2,    // total vests linearly between stream_start and stream_end
+    Hourly = 3,    // budget paid out against approved time logs
+}
+
+// How an escrow's deposit reaches the contract
+pub enum Funding {
+    Native,    // msg.value
+    Allowance, // transferFrom against a prior approve or EIP-2612 permit
+    Deferred,  // nothing now, milestones are funded later with fund_milestone
+    MultiToken(Vec<Address>), // token per milestone: msg.value for native, transferFrom for the rest
+    Parent,   // nothing, a parent milestone's locked amount covers it
+    Hourly { budget: U256 }, // no milestones, the budget is deposited like Native or Allowance
+    Permit2 { nonce: U256, deadline: U256, signature: Vec<u8> }, // Permit2 signature transfer
 }
 
 // Storage structs
@@ -39,6 +73,81 @@ sol_storage! {
         uint256 disputed_at;
         address disputed_by;
         string dispute_reason;
+        bool funded; // incremental escrows: deposit received for this milestone
+        address token; // multi-token escrows only, others use the escrow token
+        uint256 subcontracted; // held or spent by child escrows, not payable here
+        uint256 open_children;
+        uint256 subcontract_allowance; // set by the depositor, caps what child escrows may take
+    }
+
+    pub struct MilestoneSplit {
+        address[] recipients;
+        uint256[] shares_bps;
+    }
+
+    pub struct EscrowTokenTotals {
+        uint256 total;
+        uint256 paid;
+        uint256 refunded;
+    }
+
+    pub struct TimeLog {
+        uint256 hours;
+        bytes32 description_hash;
+    }
+
+    pub struct Evidence {
+        address submitter;
+        string content_ref; // content hash or IPFS CID
+        string note;
+        uint256 submitted_at;
+    }
+
+    pub struct Dispute {
+        uint256 response_deadline;
+        bool counterparty_responded;
+        Evidence[] evidence;
+        address[] voters;
+        mapping(address => bool) voted;
+        mapping(address => uint256) vote_amounts;
+        uint256 depositor_offer;
+        bool depositor_offered;
+        uint256 beneficiary_offer;
+        bool beneficiary_offered;
+        bool ruling_pending;
+        uint256 ruled_amount;
+        uint256 ruled_at;
+        address[] ruling_panel;
+        bool appealed;
+        address appellant;
+        uint256 appealed_at;
+        uint256 appeal_bond;
+        address[] appeal_panel;
+        mapping(address => bool) appeal_panel_member;
+        uint256 panel_seed_block; // L2 block seeding the pending panel draw, zero when none
+    }
+
+    pub struct ArbiterInfo {
+        uint256 stake;
+        uint256 pending_unstake;
+        uint256 unstake_available_at;
+        uint256 cases_handled;
+        uint256 cases_overturned;
+        uint256 total_slashed;
+    }
+
+    pub struct ArbiterReplacement {
+        address old_arbiter;
+        address new_arbiter;
+        address proposed_by;
+    }
+
+    pub struct TokenConfig {
+        uint256 min_escrow;
+        uint256 max_escrow;
+        uint256 max_total_escrowed;
+        uint8 decimals;
+        string symbol;
     }
 
     pub struct Application {
@@ -53,18 +162,38 @@ sol_storage! {
         address depositor;
         address beneficiary;
         address[] arbiters;
+        bool auto_arbiters;
         uint8 required_confirmations;
         address token;
         uint256 total_amount;
         uint256 paid_amount;
+        uint256 refunded_amount;
         uint256 deadline;
         uint8 status;
+        uint256 open_disputes;
+        uint256 arbitration_fee;
+        uint256 arbitration_fee_bps;
         bool work_started;
         uint256 created_at;
         uint256 milestone_count;
         bool is_open_job;
         string project_title;
         string project_description;
+        uint8 escrow_type;
+        uint256 started_at;
+        uint256 period_interval; // retainers: seconds between period releases
+        uint256 stream_start;
+        uint256 stream_end;
+        uint256 hourly_rate;
+        uint256 logged_amount; // hourly: budget claimed by submitted time logs
+        bool logs_closed;
+        bool incremental; // milestones are funded one at a time with fund_milestone
+        uint256 funded_amount;
+        bool multi_token; // milestones carry their own token
+        address[] tokens; // multi-token escrows: distinct milestone tokens
+        bool is_child; // funded from a parent milestone
+        uint256 parent_id;
+        uint256 parent_milestone;
     }
 }
 